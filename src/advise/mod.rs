@@ -1,35 +1,470 @@
-use crate::traits::{Advise, Configur};
+pub mod openai;
+
+use crate::{
+    error::Error,
+    shared::{ConversationMode, FewShotExample, ModelSettings, ModelSource, Session, Turn},
+    traits::{Advise, AdviseStream, Configur, Converse},
+};
 use core::str;
+use futures_util::{future::Either, stream, Stream};
 use kalosm::language::*;
+use openai::{OpenAiAdvisor, OpenAiConfig};
+use std::env;
+
+/// Approximate number of characters per token used to estimate the context size.
+const CHARS_PER_TOKEN: usize = 4;
 
+/// Chat markers of the Llama 3 instruct template used by the models loaded from the Hugging Face repository.
+const LLAMA_3_CHAT_MARKERS: ChatMarkers = ChatMarkers {
+    system_prompt_marker: "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n",
+    end_system_prompt_marker: "<|eot_id|>",
+    user_marker: "<|start_header_id|>user<|end_header_id|>\n",
+    end_user_marker: "<|eot_id|>",
+    assistant_marker: "<|start_header_id|>assistant<|end_header_id|>\n",
+    end_assistant_marker: "<|eot_id|>",
+};
+
+/// Advisor running the Llama chat model locally with kalosm.
+/// The kalosm 0.3 sampler takes only the temperature from the model settings,
+/// top-p and the seed are applied by the OpenAI backend only.
+/// The maximum tokens reserve the room for the reply in the context.
 pub struct Advisor {
+    model: Llama,
+    model_name: String,
+    prompt: String,
+    few_shot: Vec<FewShotExample>,
+    mode: ConversationMode,
+    sampler: GenerationParameters,
+    max_tokens: usize,
+    context_size: usize,
+    context_used: usize,
+    turns: Vec<Turn>,
     chat: Chat,
 }
 
 impl Advisor {
-    pub async fn try_new(configurator: impl Configur) -> Result<Self, String> {
-        let prompt = configurator.prompt();
-        let model = match Llama::new_chat().await {
+    pub async fn try_new(configurator: impl Configur) -> Result<Self, Error> {
+        let settings = configurator.model_settings();
+        let model = match Llama::builder()
+            .with_source(llama_source(&settings))
+            .build()
+            .await
+        {
             Ok(m) => Ok(m),
-            Err(e) => Err(e.to_string()),
+            Err(e) => Err(Error::ModelLoad(e.to_string())),
         }?;
-        let chat = Chat::builder(model).with_system_prompt(prompt).build();
 
-        Ok(Self { chat })
+        let model_name = match settings.source() {
+            ModelSource::HuggingFace { .. } => {
+                format!("{} ({})", settings.source(), settings.quantization())
+            }
+            source => source.to_string(),
+        };
+
+        Ok(Self::from_model(model, model_name, configurator))
+    }
+
+    /// Creates the advisor with the other prompt and sampling sharing the loaded model,
+    /// the model source and quantization of the configuration are not used.
+    pub fn with_configuration(&self, configurator: impl Configur) -> Self {
+        Self::from_model(self.model.clone(), self.model_name.clone(), configurator)
+    }
+
+    fn from_model(model: Llama, model_name: String, configurator: impl Configur) -> Self {
+        let prompt = configurator.prompt();
+        let settings = configurator.model_settings();
+        let sampler =
+            GenerationParameters::default().with_temperature(*settings.temperature() as f32);
+
+        let few_shot = configurator.few_shot();
+        let chat = new_chat(&model, &prompt, &few_shot, &[], &sampler);
+
+        Self {
+            model,
+            model_name,
+            context_used: prefix_tokens(&prompt, &few_shot),
+            prompt,
+            few_shot,
+            mode: configurator.conversation_mode(),
+            sampler,
+            max_tokens: *settings.max_tokens() as usize,
+            context_size: *settings.context_size() as usize,
+            turns: vec![],
+            chat,
+        }
+    }
+
+    /// Name of the loaded model.
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Resets the conversation when it is stateless or trims the oldest turns when the message would not fit in to the context.
+    fn prepare(&mut self, msg: &str) {
+        if self.mode == ConversationMode::Stateless {
+            self.reset();
+            return;
+        }
+        let expected = (msg.len() / CHARS_PER_TOKEN) + self.max_tokens;
+        if self.context_used + expected > self.context_size {
+            self.trim(expected);
+        }
+    }
+
+    /// Starts the new conversation from the system prompt and the few-shot examples.
+    fn reset(&mut self) {
+        self.turns.clear();
+        self.rebuild();
+    }
+
+    /// Drops the oldest turns until the `expected` tokens fit in to the context and rebuilds the chat.
+    fn trim(&mut self, expected: usize) {
+        let budget = self
+            .context_size
+            .saturating_sub(expected + prefix_tokens(&self.prompt, &self.few_shot));
+        let mut kept = turns_tokens(&self.turns);
+        let mut dropped = 0;
+        while kept > budget && dropped < self.turns.len() {
+            kept -= turn_tokens(&self.turns[dropped]);
+            dropped += 1;
+        }
+        self.turns.drain(..dropped);
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        self.chat = new_chat(
+            &self.model,
+            &self.prompt,
+            &self.few_shot,
+            &self.turns,
+            &self.sampler,
+        );
+        self.context_used = prefix_tokens(&self.prompt, &self.few_shot) + turns_tokens(&self.turns);
+    }
+}
+
+/// Approximate number of tokens left for a single message in the new conversation,
+/// that is the context without the reply, the system prompt and the few-shot examples.
+///
+/// # Returns
+///
+/// * Number of tokens, 0 if the prompt alone does not fit in to the context.
+pub fn message_budget(
+    settings: &ModelSettings,
+    prompt: &str,
+    few_shot: &[FewShotExample],
+) -> usize {
+    (*settings.context_size() as usize)
+        .saturating_sub(*settings.max_tokens() as usize + prefix_tokens(prompt, few_shot))
+}
+
+/// Cuts the text to the approximate number of tokens on the character boundary.
+pub fn truncate_to_tokens(text: &str, tokens: usize) -> &str {
+    let mut end = tokens.saturating_mul(CHARS_PER_TOKEN);
+    if text.len() <= end {
+        return text;
+    }
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn prefix_tokens(prompt: &str, few_shot: &[FewShotExample]) -> usize {
+    few_shot.iter().fold(prompt.len(), |acc, e| {
+        acc + e.user().len() + e.assistant().len()
+    }) / CHARS_PER_TOKEN
+}
+
+fn turn_tokens(turn: &Turn) -> usize {
+    (turn.user().len() + turn.assistant().len()) / CHARS_PER_TOKEN
+}
+
+fn turns_tokens(turns: &[Turn]) -> usize {
+    turns.iter().map(turn_tokens).sum()
+}
+
+fn new_chat(
+    model: &Llama,
+    prompt: &str,
+    few_shot: &[FewShotExample],
+    turns: &[Turn],
+    sampler: &GenerationParameters,
+) -> Chat {
+    let builder = Chat::builder(model.clone())
+        .with_system_prompt(prompt)
+        .with_sampler(sampler.clone().sampler());
+    let history = chat_history(prompt, few_shot, turns);
+    // kalosm 0.3 feeds the builder system prompt only before the empty history or the history starting
+    // with the system prompt, the history without it would drop the prompt, so the prompt is repeated.
+    if history.len() > 1 {
+        builder.with_initial_history(history)
+    } else {
+        builder
+    }
+    .build()
+}
+
+/// History seeding the chat with the system prompt, the few-shot examples and the turns in this order.
+fn chat_history(prompt: &str, few_shot: &[FewShotExample], turns: &[Turn]) -> Vec<ChatHistoryItem> {
+    let examples = few_shot.iter().map(|e| (e.user(), e.assistant()));
+    let exchanges = examples
+        .chain(turns.iter().map(|t| (t.user(), t.assistant())))
+        .flat_map(|(user, assistant)| {
+            [
+                ChatHistoryItem::new(MessageType::UserMessage, user),
+                ChatHistoryItem::new(MessageType::ModelAnswer, assistant),
+            ]
+        });
+    [ChatHistoryItem::new(MessageType::SystemPrompt, prompt)]
+        .into_iter()
+        .chain(exchanges)
+        .collect()
+}
+
+fn llama_source(settings: &ModelSettings) -> LlamaSource {
+    match settings.source() {
+        ModelSource::Llama3_1_8bChat => LlamaSource::llama_3_1_8b_chat(),
+        ModelSource::Llama3_2_1bChat => LlamaSource::llama_3_2_1b_chat(),
+        ModelSource::Llama3_2_3bChat => LlamaSource::llama_3_2_3b_chat(),
+        ModelSource::Mistral7bInstruct => LlamaSource::mistral_7b_instruct_2(),
+        ModelSource::Phi3_5MiniInstruct => LlamaSource::phi_3_5_mini_4k_instruct(),
+        ModelSource::Qwen2_5_7bInstruct => LlamaSource::qwen_2_5_7b_instruct(),
+        ModelSource::HuggingFace {
+            repo,
+            file_stem,
+            tokenizer_repo,
+        } => LlamaSource::new(
+            FileSource::huggingface(
+                repo.to_string(),
+                "main".to_string(),
+                format!("{file_stem}-{}.gguf", settings.quantization()),
+            ),
+            FileSource::huggingface(
+                tokenizer_repo.to_string(),
+                "main".to_string(),
+                "tokenizer.json".to_string(),
+            ),
+        )
+        .with_chat_markers(LLAMA_3_CHAT_MARKERS),
     }
 }
 
 impl Advise for Advisor {
-    async fn advise_about(&mut self, msg: &str) -> Result<String, String> {
+    async fn advise_about(&mut self, msg: &str) -> Result<String, Error> {
+        self.prepare(msg);
+
         let mut response_stream = self.chat.add_message(msg);
         let mut bytes: Vec<u8> = Vec::new();
         if let Err(e) = response_stream.write_to(&mut bytes).await {
-            return Err(e.to_string());
+            return Err(Error::Generation(e.to_string()));
         }
 
         match str::from_utf8(&bytes) {
-            Ok(m) => Ok(m.into()),
-            Err(e) => Err(e.to_string()),
+            Ok(m) => {
+                self.context_used += (msg.len() + m.len()) / CHARS_PER_TOKEN;
+                self.turns.push(Turn::new(msg, m));
+                Ok(m.into())
+            }
+            Err(e) => Err(Error::Generation(e.to_string())),
         }
     }
 }
+
+/// State of the local reply stream, the turn is recorded once the reply ends.
+struct ReplyStream<'a> {
+    advisor: &'a mut Advisor,
+    msg: String,
+    chunks: ChannelTextStream,
+    reply: String,
+    /// The turn is closed, nothing more is delivered.
+    done: bool,
+}
+
+impl ReplyStream<'_> {
+    fn finish(&mut self) {
+        self.done = true;
+        let reply = std::mem::take(&mut self.reply);
+        self.advisor.context_used += (self.msg.len() + reply.len()) / CHARS_PER_TOKEN;
+        self.advisor.turns.push(Turn::new(&self.msg, &reply));
+    }
+}
+
+impl Drop for ReplyStream<'_> {
+    /// Closes the turn of the cancelled stream, the chunks delivered so far are recorded as the reply
+    /// and the message without any reply is dropped. The chat is seeded again with the recorded turns,
+    /// since kalosm keeps the cancelled message and the partial reply in its own history.
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        if !self.reply.is_empty() {
+            self.finish();
+        }
+        self.advisor.rebuild();
+    }
+}
+
+impl AdviseStream for Advisor {
+    fn advise_stream(&mut self, msg: &str) -> impl Stream<Item = Result<String, Error>> + '_ {
+        self.prepare(msg);
+        let state = ReplyStream {
+            chunks: self.chat.add_message(msg),
+            advisor: self,
+            msg: msg.to_string(),
+            reply: String::new(),
+            done: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            match state.chunks.next().await {
+                Some(chunk) => {
+                    state.reply.push_str(&chunk);
+                    Some((Ok(chunk), state))
+                }
+                None => {
+                    state.finish();
+                    None
+                }
+            }
+        })
+    }
+}
+
+impl Converse for Advisor {
+    fn session(&self) -> Session {
+        Session::new(&self.prompt, self.turns.clone())
+    }
+
+    fn restore(&mut self, session: Session) {
+        self.prompt = session.system_prompt().to_string();
+        self.turns = session.turns().to_vec();
+        self.trim(self.max_tokens);
+    }
+}
+
+/// Advisor backed by one of the supported LLM backends.
+pub enum LlmAdvisor {
+    /// Model loaded locally with kalosm.
+    Local(Advisor),
+    /// Model served by the OpenAI compatible HTTP endpoint.
+    OpenAi(OpenAiAdvisor),
+}
+
+impl LlmAdvisor {
+    /// Creates the advisor with the backend selected by `ADVISE_BACKEND` environment variable,
+    /// `local` (default) loads kalosm Llama and `openai` talks to the endpoint configured with `OpenAiConfig::try_from_env`.
+    pub async fn try_new(configurator: impl Configur) -> Result<Self, Error> {
+        match env::var("ADVISE_BACKEND").as_deref() {
+            Err(_) | Ok("local") => Ok(Self::Local(Advisor::try_new(configurator).await?)),
+            Ok("openai") => Ok(Self::OpenAi(OpenAiAdvisor::new(
+                OpenAiConfig::try_from_env()?,
+                configurator,
+            ))),
+            Ok(backend) => Err(Error::Config(format!(
+                "Unknown ADVISE_BACKEND [ {backend} ], expected local or openai"
+            ))),
+        }
+    }
+}
+
+impl LlmAdvisor {
+    /// Creates the advisor with the other prompt and sampling on the same backend,
+    /// the local model is shared rather than loaded again.
+    pub fn with_configuration(&self, configurator: impl Configur) -> Self {
+        match self {
+            Self::Local(advisor) => Self::Local(advisor.with_configuration(configurator)),
+            Self::OpenAi(advisor) => Self::OpenAi(advisor.with_configuration(configurator)),
+        }
+    }
+
+    /// Name of the model behind the advisor.
+    pub fn model_name(&self) -> &str {
+        match self {
+            Self::Local(advisor) => advisor.model_name(),
+            Self::OpenAi(advisor) => advisor.model_name(),
+        }
+    }
+}
+
+impl Advise for LlmAdvisor {
+    async fn advise_about(&mut self, msg: &str) -> Result<String, Error> {
+        match self {
+            Self::Local(advisor) => advisor.advise_about(msg).await,
+            Self::OpenAi(advisor) => advisor.advise_about(msg).await,
+        }
+    }
+}
+
+impl AdviseStream for LlmAdvisor {
+    fn advise_stream(&mut self, msg: &str) -> impl Stream<Item = Result<String, Error>> + '_ {
+        match self {
+            Self::Local(advisor) => Either::Left(advisor.advise_stream(msg)),
+            Self::OpenAi(advisor) => Either::Right(advisor.advise_stream(msg)),
+        }
+    }
+}
+
+impl Converse for LlmAdvisor {
+    fn session(&self) -> Session {
+        match self {
+            Self::Local(advisor) => advisor.session(),
+            Self::OpenAi(advisor) => advisor.session(),
+        }
+    }
+
+    fn restore(&mut self, session: Session) {
+        match self {
+            Self::Local(advisor) => advisor.restore(session),
+            Self::OpenAi(advisor) => advisor.restore(session),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::FewShotExampleBuilder;
+
+    #[test]
+    fn it_should_fit_message_in_to_context() {
+        let settings = ModelSettings::deterministic();
+        let budget = message_budget(&settings, &"p".repeat(400), &[]);
+        assert_eq!(
+            budget,
+            *settings.context_size() as usize - *settings.max_tokens() as usize - 100
+        );
+
+        assert_eq!(truncate_to_tokens("short", 10), "short");
+        assert_eq!(truncate_to_tokens("abcdefghij", 2), "abcdefgh");
+        assert_eq!(truncate_to_tokens("ąćęłńóśź", 1), "ąć");
+        assert_eq!(truncate_to_tokens("text", 0), "");
+    }
+
+    #[test]
+    fn it_should_seed_chat_with_prompt_examples_and_turns() {
+        let few_shot = vec![FewShotExampleBuilder::default()
+            .user("Is BTC up?".to_string())
+            .assistant("Yes.".to_string())
+            .build()
+            .unwrap()];
+        let turns = vec![Turn::new("And ETH?", "No.")];
+
+        let history: Vec<(MessageType, String)> = chat_history("Be brief.", &few_shot, &turns)
+            .iter()
+            .map(|item| (item.ty(), item.contents().to_string()))
+            .collect();
+
+        assert_eq!(
+            history,
+            vec![
+                (MessageType::SystemPrompt, "Be brief.".to_string()),
+                (MessageType::UserMessage, "Is BTC up?".to_string()),
+                (MessageType::ModelAnswer, "Yes.".to_string()),
+                (MessageType::UserMessage, "And ETH?".to_string()),
+                (MessageType::ModelAnswer, "No.".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,752 @@
+use super::CHARS_PER_TOKEN;
+use crate::{
+    error::Error,
+    shared::{ConversationMode, ModelSettings, Session, Turn},
+    traits::{Advise, AdviseStream, Configur, Converse},
+};
+use derive_builder::Builder;
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, env, pin::Pin};
+
+const DEFAULT_BASE_URL: &str = "http://localhost:8080/v1";
+const DEFAULT_MODEL: &str = "default";
+const CHAT_COMPLETIONS_PATH: &str = "/chat/completions";
+const SSE_DATA_PREFIX: &str = "data:";
+const SSE_DONE: &str = "[DONE]";
+
+/// Configuration of the OpenAI compatible chat completions endpoint.
+/// The sampling is taken from the `ModelSettings` of the advisor like in the local backend.
+#[derive(Debug, Clone, PartialEq, Builder)]
+pub struct OpenAiConfig {
+    /// Base URL of the API including the version, such as `http://localhost:8080/v1`.
+    #[builder(default = "DEFAULT_BASE_URL.to_string()")]
+    base_url: String,
+    #[builder(default = "DEFAULT_MODEL.to_string()")]
+    model: String,
+    /// Optional bearer token, local inference servers usually do not require it.
+    #[builder(default)]
+    api_key: Option<String>,
+}
+
+impl OpenAiConfig {
+    /// Creates the configuration from `OPENAI_BASE_URL`, `OPENAI_MODEL` and `OPENAI_API_KEY` environment variables.
+    /// Missing variables fall back to the defaults.
+    pub fn try_from_env() -> Result<Self, Error> {
+        let mut builder = OpenAiConfigBuilder::default();
+        if let Ok(base_url) = env::var("OPENAI_BASE_URL") {
+            builder.base_url(base_url);
+        }
+        if let Ok(model) = env::var("OPENAI_MODEL") {
+            builder.model(model);
+        }
+        builder.api_key(env::var("OPENAI_API_KEY").ok());
+
+        builder.build().map_err(|e| Error::Config(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+impl Message {
+    fn new(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    temperature: f64,
+    top_p: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    max_tokens: u32,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: Delta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, reqwest::Error>> + Send>>;
+
+/// State of the server sent events stream of the chat completion chunks.
+struct ChunkStream<'a> {
+    messages: &'a mut Vec<Message>,
+    request: Option<reqwest::RequestBuilder>,
+    body: Option<ByteStream>,
+    buffer: Vec<u8>,
+    chunks: VecDeque<String>,
+    reply: String,
+    /// The body ended, the queued chunks are still delivered.
+    ended: bool,
+    /// The turn is closed, nothing more is delivered.
+    done: bool,
+}
+
+impl ChunkStream<'_> {
+    /// Parses the complete lines from the buffer in to the text chunks.
+    ///
+    /// # Returns
+    ///
+    /// * Success `true` if the stream is finished or `Error::Provider` if the chunk is malformed.
+    fn parse_lines(&mut self) -> Result<bool, Error> {
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix(SSE_DATA_PREFIX) else {
+                continue;
+            };
+            let data = data.trim();
+            if data == SSE_DONE {
+                return Ok(true);
+            }
+            let chunk: ChatCompletionChunk =
+                serde_json::from_str(data).map_err(|e| Error::Provider(e.to_string()))?;
+            for content in chunk.choices.into_iter().filter_map(|c| c.delta.content) {
+                self.reply.push_str(&content);
+                self.chunks.push_back(content);
+            }
+        }
+        Ok(false)
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        self.messages
+            .push(Message::new("assistant", &std::mem::take(&mut self.reply)));
+    }
+
+    fn fail(&mut self, e: Error) -> Error {
+        self.done = true;
+        self.chunks.clear();
+        self.messages.pop();
+        e
+    }
+}
+
+impl Drop for ChunkStream<'_> {
+    /// Closes the turn of the cancelled stream so the conversation keeps alternating the roles,
+    /// the chunks delivered so far are recorded as the reply and the message without any reply is removed.
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let undelivered: usize = self.chunks.iter().map(String::len).sum();
+        self.reply.truncate(self.reply.len() - undelivered);
+        if self.reply.is_empty() {
+            self.messages.pop();
+        } else {
+            self.finish();
+        }
+    }
+}
+
+/// Advisor talking to any OpenAI compatible `/v1/chat/completions` endpoint,
+/// such as llama.cpp server, vLLM or LM Studio.
+#[derive(Debug, Clone)]
+pub struct OpenAiAdvisor {
+    config: OpenAiConfig,
+    client: reqwest::Client,
+    settings: ModelSettings,
+    mode: ConversationMode,
+    /// System prompt and few-shot examples starting every conversation.
+    prefix: Vec<Message>,
+    messages: Vec<Message>,
+}
+
+impl OpenAiAdvisor {
+    pub fn new(config: OpenAiConfig, configurator: impl Configur) -> Self {
+        let mut prefix = vec![Message::new("system", &configurator.prompt())];
+        for example in configurator.few_shot() {
+            prefix.push(Message::new("user", example.user()));
+            prefix.push(Message::new("assistant", example.assistant()));
+        }
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            settings: configurator.model_settings(),
+            mode: configurator.conversation_mode(),
+            messages: prefix.clone(),
+            prefix,
+        }
+    }
+
+    /// Creates the advisor with the other prompt and sampling talking to the same endpoint.
+    pub fn with_configuration(&self, configurator: impl Configur) -> Self {
+        Self {
+            client: self.client.clone(),
+            ..Self::new(self.config.clone(), configurator)
+        }
+    }
+}
+
+impl OpenAiAdvisor {
+    /// Name of the model requested from the endpoint.
+    pub fn model_name(&self) -> &str {
+        &self.config.model
+    }
+
+    /// Appends the message to the conversation and builds the chat completion request.
+    fn request(&mut self, msg: &str, stream: bool) -> reqwest::RequestBuilder {
+        if self.mode == ConversationMode::Stateless {
+            self.messages = self.prefix.clone();
+        } else {
+            self.trim(msg);
+        }
+        self.messages.push(Message::new("user", msg));
+
+        let url = format!(
+            "{}{CHAT_COMPLETIONS_PATH}",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let body = ChatCompletionRequest {
+            model: &self.config.model,
+            messages: &self.messages,
+            temperature: *self.settings.temperature(),
+            top_p: *self.settings.top_p(),
+            seed: *self.settings.seed(),
+            max_tokens: *self.settings.max_tokens(),
+            stream,
+        };
+        let request = self.client.post(url).json(&body);
+        match self.config.api_key.as_ref() {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+impl OpenAiAdvisor {
+    /// Drops the oldest turns until the message and the reply fit in to the context.
+    fn trim(&mut self, msg: &str) {
+        let budget = (*self.settings.context_size() as usize).saturating_sub(
+            *self.settings.max_tokens() as usize
+                + messages_tokens(&self.prefix)
+                + msg.len() / CHARS_PER_TOKEN,
+        );
+        let start = self.prefix.len().min(self.messages.len());
+        let mut kept = messages_tokens(&self.messages[start..]);
+        let mut end = start;
+        while kept > budget && end < self.messages.len() {
+            // The turn is the user message with the assistant reply following it.
+            let turn_end = match self.messages.get(end + 1) {
+                Some(next) if next.role == "assistant" => end + 2,
+                _ => end + 1,
+            };
+            kept -= messages_tokens(&self.messages[end..turn_end]);
+            end = turn_end;
+        }
+        self.messages.drain(start..end);
+    }
+}
+
+fn messages_tokens(messages: &[Message]) -> usize {
+    messages.iter().map(|m| m.content.len()).sum::<usize>() / CHARS_PER_TOKEN
+}
+
+impl Advise for OpenAiAdvisor {
+    async fn advise_about(&mut self, msg: &str) -> Result<String, Error> {
+        let request = self.request(msg, false);
+
+        let reply = async {
+            request
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ChatCompletionResponse>()
+                .await?
+                .choices
+                .into_iter()
+                .next()
+                .map(|c| c.message)
+                .ok_or(Error::Provider("No choices in the response".to_string()))
+        }
+        .await;
+
+        match reply {
+            Ok(reply) => {
+                let content = reply.content.clone();
+                self.messages.push(reply);
+                Ok(content)
+            }
+            Err(e) => {
+                self.messages.pop();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl AdviseStream for OpenAiAdvisor {
+    fn advise_stream(&mut self, msg: &str) -> impl Stream<Item = Result<String, Error>> + '_ {
+        let request = self.request(msg, true);
+        let state = ChunkStream {
+            messages: &mut self.messages,
+            request: Some(request),
+            body: None,
+            buffer: Vec::new(),
+            chunks: VecDeque::new(),
+            reply: String::new(),
+            ended: false,
+            done: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(chunk) = state.chunks.pop_front() {
+                    return Some((Ok(chunk), state));
+                }
+                if state.done {
+                    return None;
+                }
+                if state.ended {
+                    state.finish();
+                    return None;
+                }
+                if let Some(request) = state.request.take() {
+                    match request.send().await.and_then(|r| r.error_for_status()) {
+                        Ok(response) => {
+                            state.body = Some(Box::pin(
+                                response.bytes_stream().map(|b| b.map(|b| b.to_vec())),
+                            ))
+                        }
+                        Err(e) => {
+                            let e = state.fail(e.into());
+                            return Some((Err(e), state));
+                        }
+                    }
+                }
+                let next = match state.body.as_mut() {
+                    Some(body) => body.next().await,
+                    None => None,
+                };
+                match next {
+                    Some(Ok(bytes)) => {
+                        state.buffer.extend(bytes);
+                        match state.parse_lines() {
+                            Ok(true) => state.ended = true,
+                            Ok(false) => {}
+                            Err(e) => {
+                                let e = state.fail(e);
+                                return Some((Err(e), state));
+                            }
+                        }
+                    }
+                    Some(Err(e)) => {
+                        let e = state.fail(e.into());
+                        return Some((Err(e), state));
+                    }
+                    None => state.ended = true,
+                }
+            }
+        })
+    }
+}
+
+impl Converse for OpenAiAdvisor {
+    fn session(&self) -> Session {
+        let system_prompt = self.prefix.first().map(|m| m.content.as_str());
+        let mut turns: Vec<Turn> = Vec::new();
+        let mut user: Option<&str> = None;
+        for message in &self.messages[self.prefix.len().min(self.messages.len())..] {
+            match message.role.as_str() {
+                "user" => {
+                    if let Some(unanswered) = user.replace(&message.content) {
+                        turns.push(Turn::new(unanswered, ""));
+                    }
+                }
+                "assistant" => {
+                    turns.push(Turn::new(user.take().unwrap_or_default(), &message.content))
+                }
+                _ => {}
+            }
+        }
+        if let Some(unanswered) = user {
+            turns.push(Turn::new(unanswered, ""));
+        }
+        Session::new(system_prompt.unwrap_or_default(), turns)
+    }
+
+    fn restore(&mut self, session: Session) {
+        let system = Message::new("system", session.system_prompt());
+        match self.prefix.first_mut() {
+            Some(message) => *message = system,
+            None => self.prefix.push(system),
+        }
+        self.messages = self.prefix.clone();
+        for turn in session.turns() {
+            self.messages.push(Message::new("user", turn.user()));
+            self.messages
+                .push(Message::new("assistant", turn.assistant()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{ConfigurationBuilder, FewShotExampleBuilder, ModelSettingsBuilder};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_partial_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    struct Prompt;
+
+    impl Configur for Prompt {
+        fn prompt(&self) -> String {
+            "You are a test assistant.".to_string()
+        }
+
+        fn model_settings(&self) -> ModelSettings {
+            ModelSettings::deterministic()
+        }
+    }
+
+    fn completion(content: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop"
+            }]
+        }))
+    }
+
+    fn config(server: &MockServer) -> OpenAiConfig {
+        OpenAiConfigBuilder::default()
+            .base_url(format!("{}/v1", server.uri()))
+            .model("llama-3".to_string())
+            .api_key(Some("secret".to_string()))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_should_send_conversation_to_chat_completions() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({
+                "model": "llama-3",
+                "temperature": 0.0,
+                "top_p": 1.0,
+                "seed": 0,
+                "max_tokens": 128,
+                "messages": [
+                    {"role": "system", "content": "You are a test assistant."},
+                    {"role": "user", "content": "Hello"}
+                ]
+            })))
+            .respond_with(completion("Hi there"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        assert_eq!(advisor.advise_about("Hello").await?, "Hi there");
+        assert_eq!(advisor.messages.len(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_start_every_message_from_few_shot_prefix_when_stateless() -> Result<(), Error>
+    {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "messages": [
+                    {"role": "system", "content": "Score the sentiment."},
+                    {"role": "user", "content": "Great news"},
+                    {"role": "assistant", "content": "positive"},
+                    {"role": "user", "content": "Second article"}
+                ]
+            })))
+            .respond_with(completion("neutral"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(completion("negative"))
+            .mount(&server)
+            .await;
+
+        let configuration = ConfigurationBuilder::default()
+            .prompt("Score the sentiment.".to_string())
+            .conversation_mode(ConversationMode::Stateless)
+            .few_shot(vec![FewShotExampleBuilder::default()
+                .user("Great news".to_string())
+                .assistant("positive".to_string())
+                .build()
+                .unwrap()])
+            .build()
+            .unwrap();
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), configuration);
+        assert_eq!(advisor.advise_about("First article").await?, "negative");
+        assert_eq!(advisor.advise_about("Second article").await?, "neutral");
+        assert_eq!(advisor.messages.len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_stream_chunks_from_server_sent_events() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        let events = [
+            r#"data: {"choices":[{"index":0,"delta":{"role":"assistant"}}]}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"content":"Hi"}}]}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"content":" there"}}]}"#,
+            "data: [DONE]",
+        ]
+        .map(|e| format!("{e}\n\n"))
+        .concat();
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        let chunks = advisor
+            .advise_stream("Hello")
+            .collect::<Vec<Result<String, Error>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<String>, Error>>()?;
+        assert_eq!(chunks, vec!["Hi".to_string(), " there".to_string()]);
+        assert_eq!(
+            advisor.messages.last(),
+            Some(&Message::new("assistant", "Hi there"))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_close_turn_when_stream_is_dropped() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        let events = [
+            r#"data: {"choices":[{"index":0,"delta":{"content":"Hi"}}]}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"content":" there"}}]}"#,
+            "data: [DONE]",
+        ]
+        .map(|e| format!("{e}\n\n"))
+        .concat();
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        drop(advisor.advise_stream("Ignored"));
+        assert_eq!(advisor.messages.len(), 1);
+
+        {
+            let mut stream = std::pin::pin!(advisor.advise_stream("Hello"));
+            assert_eq!(stream.next().await, Some(Ok("Hi".to_string())));
+        }
+        assert_eq!(
+            advisor.messages[1..],
+            [
+                Message::new("user", "Hello"),
+                Message::new("assistant", "Hi")
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_continue_restored_session() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "messages": [
+                    {"role": "system", "content": "You are a saved assistant."},
+                    {"role": "user", "content": "Is BTC up?"},
+                    {"role": "assistant", "content": "Yes."},
+                    {"role": "user", "content": "And ETH?"}
+                ]
+            })))
+            .respond_with(completion("No."))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        advisor.restore(Session::new(
+            "You are a saved assistant.",
+            vec![Turn::new("Is BTC up?", "Yes.")],
+        ));
+        advisor.advise_about("And ETH?").await?;
+
+        assert_eq!(
+            advisor.session(),
+            Session::new(
+                "You are a saved assistant.",
+                vec![
+                    Turn::new("Is BTC up?", "Yes."),
+                    Turn::new("And ETH?", "No.")
+                ]
+            )
+        );
+
+        Ok(())
+    }
+
+    struct Scorer;
+
+    impl Configur for Scorer {
+        fn prompt(&self) -> String {
+            "You are a test scorer.".to_string()
+        }
+
+        fn model_settings(&self) -> ModelSettings {
+            ModelSettingsBuilder::default()
+                .temperature(0.5)
+                .build()
+                .unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_create_advisor_with_other_configuration_on_same_endpoint(
+    ) -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "model": "llama-3",
+                "temperature": 0.5,
+                "messages": [
+                    {"role": "system", "content": "You are a test scorer."},
+                    {"role": "user", "content": "Score it."}
+                ]
+            })))
+            .respond_with(completion("0.5"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        let mut scorer = advisor.with_configuration(Scorer);
+
+        assert_eq!(scorer.advise_about("Score it.").await?, "0.5");
+        assert_eq!(
+            advisor.session().system_prompt(),
+            "You are a test assistant."
+        );
+
+        Ok(())
+    }
+
+    struct SmallContext;
+
+    impl Configur for SmallContext {
+        fn prompt(&self) -> String {
+            "You are a test assistant.".to_string()
+        }
+
+        fn model_settings(&self) -> ModelSettings {
+            ModelSettingsBuilder::default()
+                .context_size(64)
+                .max_tokens(16)
+                .build()
+                .unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_trim_oldest_turns_to_fit_context() -> Result<(), Error> {
+        let (old, recent, next) = ("a".repeat(80), "b".repeat(80), "c".repeat(40));
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "messages": [
+                    {"role": "system", "content": "You are a test assistant."},
+                    {"role": "user", "content": recent},
+                    {"role": "assistant", "content": "Recent reply of forty characters long...."},
+                    {"role": "user", "content": next}
+                ]
+            })))
+            .respond_with(completion("Ok."))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), SmallContext);
+        advisor.restore(Session::new(
+            "You are a test assistant.",
+            vec![
+                Turn::new(&old, "Old reply of forty characters long......."),
+                Turn::new(&recent, "Recent reply of forty characters long...."),
+            ],
+        ));
+        advisor.advise_about(&next).await?;
+
+        assert_eq!(advisor.session().turns().len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_classify_failed_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let mut advisor = OpenAiAdvisor::new(config(&server), Prompt);
+        assert!(matches!(
+            advisor.advise_about("Hello").await,
+            Err(Error::Unauthorized(_))
+        ));
+        assert!(matches!(
+            advisor.advise_about("Hello").await,
+            Err(Error::Http(_))
+        ));
+        assert_eq!(advisor.messages.len(), 1);
+    }
+}
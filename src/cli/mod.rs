@@ -1,120 +1,466 @@
-use crate::advise::Advisor;
+use crate::advise::{message_budget, truncate_to_tokens, LlmAdvisor};
+use crate::config::PullConfig;
+use crate::dedup::Deduplicator;
+use crate::error::Error;
+use crate::mongostore::Storage;
 use crate::newsdata_io::connector::NewsDataIoConnectorBuilder;
-use crate::newsdata_io::extractor::CoinExtractor;
+use crate::newsdata_io::extractor::{CoinExtractor, CombinedExtractor, TagExtractor};
+use crate::newsdata_io::query::NewsDataIoQueryBuilder;
 use crate::newsdata_io::validator::NewsDataIoLanguageValidator;
-use crate::traits::{Advise, Configur, Fetcher, Handler};
-use clap::Parser;
-use dotenv::dotenv;
+use crate::persona::PersonaRegistry;
+#[cfg(feature = "server")]
+use crate::push::PushModelBuilder;
+use crate::report::{compare_with_provider, summarize_by_coin};
+use crate::retry::RetryFetcherBuilder;
+use crate::sentiment::{advise_sentiment, CORRECTION_TOKENS};
+use crate::shared::{
+    ConfigurationBuilder, ConversationMode, FewShotExample, ModelSettings, ModelSettingsBuilder,
+    ModelSource, Quantization, SentimentData, SentimentLabel, Session,
+};
+use crate::traits::{AdviseStream, Converse, Fetcher, Handler, Store, ValidatorStrategy};
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::{Parser, Subcommand};
+use futures_util::{pin_mut, StreamExt};
 use inquire::Text;
+use std::collections::HashMap;
 use std::env;
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 use tokio::time::interval;
 
-const PULL_MODEL_PROMPT: &str = r#"
-Analyze the text sentiment and provide the result in JSON format like in this example:
-{"negative": 0.0, "neutral": 0.0, "positive": 0.0} where 0.0 is a real value given as a float.
-"#;
+const DEFAULT_RANGE_H: i64 = 24;
 
-const INTERVAL_S: u64 = 24 * 60 * 60 / 200; // 200 queries per day
+/// Selects the persona from the registry to preset the prompt and the few-shot examples.
+#[derive(clap::Args, Debug)]
+struct PersonaArgs {
+    /// Name of the persona file in the personas directory, `trump` for `personas/trump.toml`.
+    #[arg(long)]
+    persona: Option<String>,
+    /// Directory with the persona TOML and YAML files.
+    #[arg(long, default_value = "personas")]
+    personas_dir: PathBuf,
+    /// Value of the persona prompt variable given as `name=value`, may be repeated.
+    #[arg(long = "var", value_parser = parse_variable)]
+    variables: Vec<(String, String)>,
+}
 
-/// Handles the startup commands of a CLI program.
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+impl PersonaArgs {
+    /// Loads the selected persona and renders its prompt.
+    ///
+    /// # Returns
+    ///
+    /// * Success with the prompt and the few-shot examples, `None` if no persona is selected, or `Error` if it can not be loaded.
+    fn resolve(&self) -> Result<Option<(String, Vec<FewShotExample>)>, Error> {
+        let Some(name) = self.persona.as_ref() else {
+            return Ok(None);
+        };
+        let registry = PersonaRegistry::load_dir(&self.personas_dir)?;
+        let persona = registry.get(name)?;
+        let variables: HashMap<String, String> = self.variables.iter().cloned().collect();
+
+        Ok(Some((
+            persona.render(&variables)?,
+            persona.few_shot().to_vec(),
+        )))
+    }
+}
+
+fn parse_variable(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or(format!("Variable [ {s} ] is not in the name=value format"))
+}
+
+/// Runs the interactive chat with the model.
+#[derive(clap::Args, Debug)]
+pub struct ChatArgs {
     /// Prompt to the Advise provider to preset analyze parameters.
-    #[arg(short, long)]
-    prompt: String,
+    #[arg(
+        short,
+        long,
+        required_unless_present = "persona",
+        conflicts_with = "persona"
+    )]
+    prompt: Option<String>,
+    #[command(flatten)]
+    persona: PersonaArgs,
+    /// Model preset name or `hf:<repo>:<file_stem>:<tokenizer_repo>` for GGUF weights from Hugging Face.
+    #[arg(long, default_value = "llama-3.1-8b-chat")]
+    model: ModelSource,
+    /// Quantization of the Hugging Face GGUF weights.
+    #[arg(long, default_value = "Q4_K_M")]
+    quantization: Quantization,
+    /// Maximum number of tokens kept in the chat context.
+    #[arg(long, default_value_t = 4096)]
+    context_size: u32,
+    #[arg(long, default_value_t = 0.8)]
+    temperature: f64,
+    #[arg(long, default_value_t = 0.95)]
+    top_p: f64,
+    /// Seed of the sampler, random when not set.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Maximum number of tokens generated in a single response.
+    #[arg(long, default_value_t = 1024)]
+    max_tokens: u32,
+    /// JSON file the conversation is saved to after every reply and resumed from when it exists.
+    #[arg(long)]
+    session: Option<PathBuf>,
+}
+
+impl ChatArgs {
+    fn model_settings(&self) -> ModelSettings {
+        let mut builder = ModelSettingsBuilder::default();
+        builder
+            .source(self.model.clone())
+            .quantization(self.quantization)
+            .context_size(self.context_size)
+            .temperature(self.temperature)
+            .top_p(self.top_p)
+            .max_tokens(self.max_tokens);
+        if let Some(seed) = self.seed {
+            builder.seed(seed);
+        }
+        builder.build().unwrap_or_default()
+    }
+}
+
+const CHAT_HELP: &str = "Commands: /reset, /prompt <system prompt>, /save [file], /load [file], /history, /export markdown [file], /model, /quit";
+
+/// Slash command typed in to the chat instead of the message.
+#[derive(Debug, Clone, PartialEq)]
+enum SlashCommand {
+    /// Clears the conversation keeping the system prompt.
+    Reset,
+    /// Replaces the system prompt keeping the conversation.
+    Prompt(String),
+    /// Saves the session to the file or to the `--session` file.
+    Save(Option<PathBuf>),
+    /// Loads the session from the file or from the `--session` file.
+    Load(Option<PathBuf>),
+    /// Prints the conversation.
+    History,
+    /// Writes the conversation as Markdown to the file or prints it.
+    ExportMarkdown(Option<PathBuf>),
+    /// Prints the model in use.
+    Model,
+    /// Exits the chat.
+    Quit,
 }
 
-impl Configur for Args {
-    fn prompt(&self) -> String {
-        self.prompt.to_string()
+impl FromStr for SlashCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.trim().split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim()).filter(|a| !a.is_empty())),
+            None => (s.trim(), None),
+        };
+        let path = arg.map(PathBuf::from);
+        match name {
+            "/reset" => Ok(Self::Reset),
+            "/prompt" => arg
+                .map(|a| Self::Prompt(a.to_string()))
+                .ok_or(Error::Config(
+                    "/prompt requires the new system prompt".to_string(),
+                )),
+            "/save" => Ok(Self::Save(path)),
+            "/load" => Ok(Self::Load(path)),
+            "/history" => Ok(Self::History),
+            "/export" => match arg.map(|a| a.split_once(char::is_whitespace).unwrap_or((a, ""))) {
+                Some(("markdown", file)) => Ok(Self::ExportMarkdown(
+                    Some(file.trim())
+                        .filter(|f| !f.is_empty())
+                        .map(PathBuf::from),
+                )),
+                _ => Err(Error::Config("/export supports only markdown".to_string())),
+            },
+            "/model" => Ok(Self::Model),
+            "/quit" => Ok(Self::Quit),
+            _ => Err(Error::Config(format!("Unknown command [ {name} ]"))),
+        }
     }
 }
 
 /// Handles the CLI commands and runs the program.
-pub struct ChatRunner;
+pub struct ChatRunner {
+    args: ChatArgs,
+}
+
+impl ChatRunner {
+    pub fn new(args: ChatArgs) -> Self {
+        Self { args }
+    }
+
+    /// Executes the slash command on the advisor.
+    ///
+    /// # Returns
+    ///
+    /// * Success `true` if the chat should continue, `false` on `/quit` or `Error` if the command failed.
+    fn execute(
+        &self,
+        command: SlashCommand,
+        advise: &mut LlmAdvisor,
+        session_path: Option<&Path>,
+    ) -> Result<bool, Error> {
+        let session_file = |path: Option<PathBuf>| {
+            path.or(session_path.map(Path::to_path_buf))
+                .ok_or(Error::Config(
+                    "No file given and no --session set".to_string(),
+                ))
+        };
+        match command {
+            SlashCommand::Reset => {
+                let prompt = advise.session().system_prompt().to_string();
+                advise.restore(Session::new(&prompt, vec![]));
+                println!("Conversation cleared.");
+            }
+            SlashCommand::Prompt(prompt) => {
+                let turns = advise.session().turns().to_vec();
+                advise.restore(Session::new(&prompt, turns));
+                println!("System prompt replaced.");
+            }
+            SlashCommand::Save(path) => {
+                let path = session_file(path)?;
+                advise.session().save(&path)?;
+                println!("Session saved to {}.", path.display());
+            }
+            SlashCommand::Load(path) => {
+                let session = Session::load(&session_file(path)?)?;
+                println!("Loaded session with {} turns.", session.turns().len());
+                advise.restore(session);
+            }
+            SlashCommand::History => {
+                let session = advise.session();
+                println!("System: {}", session.system_prompt());
+                for turn in session.turns() {
+                    println!("> {}", turn.user());
+                    println!("{}", turn.assistant());
+                }
+            }
+            SlashCommand::ExportMarkdown(path) => {
+                let markdown = advise.session().to_markdown();
+                match path {
+                    Some(path) => {
+                        std::fs::write(&path, markdown)
+                            .map_err(|e| Error::Storage(format!("{}: {e}", path.display())))?;
+                        println!("Session exported to {}.", path.display());
+                    }
+                    None => println!("{markdown}"),
+                }
+            }
+            SlashCommand::Model => println!("Model: {}", advise.model_name()),
+            SlashCommand::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+}
 
 impl Handler for ChatRunner {
-    async fn run(&self) -> Result<(), String> {
-        let args = Args::parse();
+    async fn run(&self) -> Result<(), Error> {
+        let args = &self.args;
+        let session_path = args.session.clone();
+        let (prompt, few_shot) = match args.persona.resolve()? {
+            Some(persona) => persona,
+            None => (args.prompt.clone().unwrap_or_default(), vec![]),
+        };
+        let configuration = ConfigurationBuilder::default()
+            .prompt(prompt)
+            .few_shot(few_shot)
+            .model_settings(args.model_settings())
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
 
-        let mut advise = Advisor::try_new(args).await?;
+        let mut advise = LlmAdvisor::try_new(configuration).await?;
+        if let Some(path) = session_path.as_ref().filter(|p| p.exists()) {
+            let session = Session::load(path)?;
+            println!("Resumed session with {} turns.", session.turns().len());
+            advise.restore(session);
+        }
 
+        println!("{CHAT_HELP}");
         loop {
             let message = Text::new("Please write message to analyze.").prompt();
 
             let Ok(msg) = message else {
-                return Err("Program was interrupted...".to_string());
+                return Err(Error::Interrupted);
             };
-            let analyzed_msg = advise.advise_about(msg.as_ref()).await?;
-            println!("{analyzed_msg}");
+            if msg.trim_start().starts_with('/') {
+                let executed = msg.parse::<SlashCommand>().and_then(|command| {
+                    self.execute(command, &mut advise, session_path.as_deref())
+                });
+                match executed {
+                    Ok(true) => continue,
+                    Ok(false) => return Ok(()),
+                    Err(e) => {
+                        println!("{e}");
+                        println!("{CHAT_HELP}");
+                        continue;
+                    }
+                }
+            }
+            {
+                let stream = advise.advise_stream(msg.as_ref());
+                pin_mut!(stream);
+                loop {
+                    tokio::select! {
+                        chunk = stream.next() => match chunk {
+                            Some(Ok(chunk)) => {
+                                print!("{chunk}");
+                                stdout().flush().map_err(|e| Error::Generation(e.to_string()))?;
+                            }
+                            Some(Err(e)) => {
+                                print!("\n{e}");
+                                break;
+                            }
+                            None => break,
+                        },
+                        _ = tokio::signal::ctrl_c() => {
+                            print!("\n[generation cancelled]");
+                            break;
+                        }
+                    }
+                }
+            }
+            println!();
             println!("-------------");
+            if let Some(path) = session_path.as_ref() {
+                advise.session().save(path)?;
+            }
         }
     }
 }
 
+/// Pulls the news, scores their sentiment and saves them in the storage.
+#[derive(clap::Args, Debug)]
+pub struct PullArgs {
+    /// TOML file with the pull pipeline configuration, the defaults are used if not set.
+    #[arg(long, env = "PULL_CONFIG")]
+    config: Option<PathBuf>,
+    #[command(flatten)]
+    persona: PersonaArgs,
+}
+
 /// Handles the PullModel execution.
-pub struct PullModel;
+pub struct PullModel<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    store: S,
+    args: PullArgs,
+}
+
+impl<S> PullModel<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    /// Creates the PullModel that saves every analyzed article in to the `store`.
+    pub fn new(store: S, args: PullArgs) -> Self {
+        Self { store, args }
+    }
+}
 
-impl Handler for PullModel {
-    async fn run(&self) -> Result<(), String> {
-        dotenv().map_err(|e| format!("{e}"))?;
-        let api_key = env::var("NEWSDATA_IO").map_err(|e| format!("{e}"))?;
+impl<S> Handler for PullModel<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    async fn run(&self) -> Result<(), Error> {
+        let api_key = env::var("NEWSDATA_IO").map_err(|e| Error::Config(e.to_string()))?;
 
-        let args = Args {
-            prompt: PULL_MODEL_PROMPT.to_string(),
-        };
+        let config = PullConfig::try_load(self.args.config.as_deref())?;
+
+        let (prompt, few_shot) = self
+            .args
+            .persona
+            .resolve()?
+            .unwrap_or((config.prompt().to_string(), vec![]));
+        let settings = ModelSettings::deterministic();
+        // Every article starts the new conversation, the article and the correction on retry must fit in to it.
+        let article_budget =
+            message_budget(&settings, &prompt, &few_shot).saturating_sub(CORRECTION_TOKENS);
+        let configuration = ConfigurationBuilder::default()
+            .prompt(prompt)
+            .few_shot(few_shot)
+            .model_settings(settings)
+            .conversation_mode(ConversationMode::Stateless)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
 
-        let mut advise = Advisor::try_new(args).await?;
-
-        let lang_validator = NewsDataIoLanguageValidator::try_new(&vec![
-            "english".to_string(),
-            "eng".to_string(),
-            "british".to_string(),
-        ])?;
-
-        let coin_extractor = CoinExtractor::try_new(&vec![
-            "stablecoin".to_string(),
-            "stablecoins".to_string(),
-            "nft".to_string(),
-            "nfts".to_string(),
-            "bitcoin".to_string(),
-            "ethereum".to_string(),
-            "solana".to_string(),
-            "bitcoins".to_string(),
-            "ethereums".to_string(),
-            "solanas".to_string(),
-            "dogecoin".to_string(),
-            "sol".to_string(),
-            "btc".to_string(),
-            "eth".to_string(),
-            "sol".to_string(),
-            "usdt".to_string(),
-            "xrp".to_string(),
-            "bnb".to_string(),
-            "usdc".to_string(),
-            "dodge".to_string(),
-            "doge".to_string(),
-            "ada".to_string(),
-            "steth".to_string(),
-        ])?;
+        let mut advise = LlmAdvisor::try_new(configuration).await?;
+
+        let lang_validator = NewsDataIoLanguageValidator::try_new(config.languages())?;
+        let coin_extractor = CombinedExtractor::new(
+            CoinExtractor::try_with_aliases(config.assets())?,
+            TagExtractor::new(config.assets()).with_unknown_cashtags(*config.unknown_cashtags()),
+        );
+
+        let query = NewsDataIoQueryBuilder::default()
+            .keywords(config.keywords().as_str())
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
 
         let news_data_connector = NewsDataIoConnectorBuilder::default()
             .api_key(api_key)
+            .query(query)
             .lang_validator(lang_validator)
             .coin_extractor(coin_extractor)
+            .max_pages(*config.max_pages())
+            .timeout(Duration::from_secs(*config.request_timeout_s()))
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let news_data_connector = RetryFetcherBuilder::default()
+            .fetcher(news_data_connector)
+            .max_retries(*config.max_retries())
+            .base_delay(Duration::from_millis(*config.backoff_base_ms()))
+            .max_delay(Duration::from_secs(*config.backoff_max_s()))
+            .credits_per_pull(*config.max_pages() as u64)
+            .daily_credits(Some(*config.daily_credits()))
             .build()
-            .map_err(|e| format!("{e}"))?;
+            .map_err(|e| Error::Config(e.to_string()))?;
 
-        let mut interval = interval(Duration::from_secs(INTERVAL_S));
+        let language_detector = config.language_detector()?;
+
+        let now_ms = Utc::now().timestamp_millis() as u64;
+        let mut deduplicator = Deduplicator::try_from_store(
+            &self.store,
+            now_ms,
+            config.dedup_window_h() * 60 * 60 * 1000,
+            *config.max_simhash_distance(),
+        )
+        .await?;
+
+        let mut interval = interval(Duration::from_secs(*config.interval_s()));
         loop {
             interval.tick().await;
-            let results = news_data_connector.pull().await?;
+            let results = match news_data_connector.pull().await {
+                Ok(results) => results,
+                Err(e) if e.is_transient() => {
+                    println!("Failed to pull the news, retrying on the next tick: {e}");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let results = match language_detector.as_ref() {
+                Some(detector) => results
+                    .into_iter()
+                    .filter(|r| detector.is_valid(&format!("{}\n{}", r.title(), r.body())))
+                    .collect(),
+                None => results,
+            };
             for r in results.iter() {
-                let resp = advise
-                    .advise_about(format!("{}\n{}", r.title(), r.text()).as_str())
-                    .await?;
+                if let Some(duplicate) = deduplicator.check(r) {
+                    println!("Skipping article [ {} ], {duplicate}", r.id());
+                    continue;
+                }
+                let article = format!("{}\n{}", r.title(), r.body());
+                let resp = advise_sentiment(
+                    &mut advise,
+                    truncate_to_tokens(&article, article_budget),
+                    *config.sentiment_retries(),
+                )
+                .await;
                 println!("===============================[ NEXT ARTICLE DATA ]===============================");
                 println!(
                     "Title:{}\nText:\n{}\nCoins: {:?}\nKeywords: {:?}\nLink: {:?}\n",
@@ -126,10 +472,354 @@ impl Handler for PullModel {
                 );
                 println!("===============================[ NEXT ARTICLE DATA ]===============================\n");
                 println!("_______________________________[ SENTIMENT ANALITICS ]_____________________________");
-                println!("{resp}");
+                match resp.as_ref() {
+                    Ok(sentiment) => println!(
+                        "Negative: {:.2}\nNeutral: {:.2}\nPositive: {:.2}",
+                        sentiment.negative(),
+                        sentiment.neutral(),
+                        sentiment.positive(),
+                    ),
+                    Err(e) => println!("Sentiment not available: {e}"),
+                }
                 println!("_______________________________[ SENTIMENT ANALITICS ]_____________________________");
                 println!("\n\n");
+
+                let sentiment = match resp {
+                    Ok(sentiment) => sentiment,
+                    Err(e) if e.is_transient() => continue,
+                    Err(e) => return Err(e),
+                };
+                deduplicator.remember(r);
+                let data = SentimentData::from((r, sentiment));
+                if let Err(e) = self.store.save(&data).await {
+                    println!("Failed to store sentiment of article [ {} ]: {e}", r.id());
+                }
             }
         }
     }
 }
+
+/// Reads the sentiment data from the storage and prints it as JSON lines.
+#[derive(clap::Args, Debug)]
+pub struct QueryArgs {
+    /// Id of the stored sentiment data, the time range is ignored when given.
+    #[arg(long)]
+    id: Option<String>,
+    #[command(flatten)]
+    range: RangeArgs,
+}
+
+/// Prints the average sentiment per coin of the articles published in the time range.
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    #[command(flatten)]
+    range: RangeArgs,
+    /// Compares the model sentiment with the sentiment given by the news provider instead.
+    #[arg(long)]
+    provider: bool,
+    /// Number of the disagreements printed with the provider comparison.
+    #[arg(long, default_value_t = 5, requires = "provider")]
+    samples: usize,
+}
+
+/// Publication time range of the stored sentiment data.
+#[derive(clap::Args, Debug)]
+struct RangeArgs {
+    /// Inclusive start as RFC 3339 date or [ ms ] since the epoch, 24 hours ago if not set.
+    #[arg(long, value_parser = parse_time)]
+    from: Option<u64>,
+    /// Inclusive end as RFC 3339 date or [ ms ] since the epoch, unbounded if not set.
+    #[arg(long, value_parser = parse_time)]
+    to: Option<u64>,
+}
+
+impl RangeArgs {
+    /// Reads the stored sentiment data published in the range.
+    async fn read<S>(&self, store: &S) -> Result<Vec<SentimentData>, Error>
+    where
+        S: for<'a> Store<'a, SentimentData>,
+    {
+        let from = self
+            .from
+            .unwrap_or((Utc::now() - TimeDelta::hours(DEFAULT_RANGE_H)).timestamp_millis() as u64);
+        let data = store.read_from_time(from as u128).await?;
+        Ok(data
+            .into_iter()
+            .filter(|d| self.to.is_none_or(|to| *d.created_at() <= to))
+            .collect())
+    }
+}
+
+fn parse_time(s: &str) -> Result<u64, String> {
+    if let Ok(ms) = s.parse::<u64>() {
+        return Ok(ms);
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.timestamp_millis() as u64)
+        .map_err(|e| format!("Time [ {s} ] is neither RFC 3339 date nor [ ms ]: {e}"))
+}
+
+/// Serves the Push Agent REST API.
+#[cfg(feature = "server")]
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// Address the server listens on.
+    #[arg(long, env = "PUSH_MODEL_ADDR")]
+    addr: Option<std::net::SocketAddr>,
+    /// Prompt of the model answering on the advise endpoint.
+    #[arg(long, env = "PUSH_MODEL_PROMPT")]
+    prompt: Option<String>,
+}
+
+/// Subcommand of the uncle program.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    Chat(ChatArgs),
+    Pull(PullArgs),
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+    Query(QueryArgs),
+    Report(ReportArgs),
+}
+
+/// Analytics tools on top of LLM's.
+#[derive(Parser, Debug)]
+#[command(name = "uncle", version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+impl Cli {
+    /// Runs the Handler of the selected subcommand.
+    ///
+    /// # Returns
+    ///
+    /// * Success if the handler finished or `Error` describing the failure.
+    pub async fn run(self) -> Result<(), Error> {
+        match self.command {
+            Command::Chat(args) => ChatRunner::new(args).run().await,
+            Command::Pull(args) => {
+                PullModel::new(Storage::try_from_env().await?, args)
+                    .run()
+                    .await
+            }
+            #[cfg(feature = "server")]
+            Command::Serve(args) => {
+                let mut builder = PushModelBuilder::default().store(Storage::try_from_env().await?);
+                if let Some(addr) = args.addr {
+                    builder = builder.addr(addr);
+                }
+                if let Some(prompt) = args.prompt {
+                    builder = builder.prompt(prompt);
+                }
+                builder
+                    .build()
+                    .map_err(|e| Error::Config(e.to_string()))?
+                    .run()
+                    .await
+            }
+            Command::Query(args) => {
+                QueryRunner::new(Storage::try_from_env().await?, args)
+                    .run()
+                    .await
+            }
+            Command::Report(args) => {
+                ReportRunner::new(Storage::try_from_env().await?, args)
+                    .run()
+                    .await
+            }
+        }
+    }
+}
+
+/// Handles the query of the stored sentiment data.
+pub struct QueryRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    store: S,
+    args: QueryArgs,
+}
+
+impl<S> QueryRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    pub fn new(store: S, args: QueryArgs) -> Self {
+        Self { store, args }
+    }
+}
+
+impl<S> Handler for QueryRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    async fn run(&self) -> Result<(), Error> {
+        let data = match self.args.id.as_ref() {
+            Some(id) => vec![self.store.read_by_id(id).await?],
+            None => self.args.range.read(&self.store).await?,
+        };
+        for d in data {
+            let json = serde_json::to_string(&d).map_err(|e| Error::Storage(e.to_string()))?;
+            println!("{json}");
+        }
+        Ok(())
+    }
+}
+
+/// Handles the sentiment report of the stored sentiment data.
+pub struct ReportRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    store: S,
+    args: ReportArgs,
+}
+
+impl<S> ReportRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    pub fn new(store: S, args: ReportArgs) -> Self {
+        Self { store, args }
+    }
+}
+
+impl<S> Handler for ReportRunner<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    async fn run(&self) -> Result<(), Error> {
+        let data = self.args.range.read(&self.store).await?;
+        if self.args.provider {
+            print_agreement(&data, self.args.samples);
+            return Ok(());
+        }
+        println!(
+            "{:<12} {:>8} {:>9} {:>9} {:>9}",
+            "coin", "articles", "negative", "neutral", "positive"
+        );
+        for coin in summarize_by_coin(&data) {
+            println!(
+                "{:<12} {:>8} {:>9.3} {:>9.3} {:>9.3}",
+                coin.coin(),
+                coin.articles(),
+                coin.negative(),
+                coin.neutral(),
+                coin.positive()
+            );
+        }
+        Ok(())
+    }
+}
+
+fn print_agreement(data: &[SentimentData], samples: usize) {
+    let agreement = compare_with_provider(data, samples);
+    println!(
+        "Articles with provider sentiment: {} of {}",
+        agreement.articles(),
+        data.len()
+    );
+    if *agreement.articles() == 0 {
+        return;
+    }
+    println!("Accuracy: {:.3}", agreement.accuracy());
+    match agreement.correlation() {
+        Some(correlation) => println!("Polarity correlation: {correlation:.3}"),
+        None => println!("Polarity correlation: n/a"),
+    }
+
+    println!(
+        "\n{:<18} {:>9} {:>9} {:>9}",
+        "provider \\ model", "negative", "neutral", "positive"
+    );
+    for (label, row) in SentimentLabel::ALL.iter().zip(agreement.confusion()) {
+        println!(
+            "{:<18} {:>9} {:>9} {:>9}",
+            label.to_string(),
+            row[0],
+            row[1],
+            row[2]
+        );
+    }
+
+    if !agreement.disagreements().is_empty() {
+        println!("\nLargest disagreements:");
+    }
+    for d in agreement.disagreements() {
+        println!(
+            "{} provider: {} model: {} gap: {:.3} {}",
+            d.resource_id(),
+            d.provider(),
+            d.model(),
+            d.gap(),
+            d.title()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_slash_commands() -> Result<(), Error> {
+        assert_eq!("/reset".parse::<SlashCommand>()?, SlashCommand::Reset);
+        assert_eq!(
+            "/prompt You are a trader.".parse::<SlashCommand>()?,
+            SlashCommand::Prompt("You are a trader.".to_string())
+        );
+        assert_eq!("/save".parse::<SlashCommand>()?, SlashCommand::Save(None));
+        assert_eq!(
+            "/load chat.json".parse::<SlashCommand>()?,
+            SlashCommand::Load(Some(PathBuf::from("chat.json")))
+        );
+        assert_eq!(
+            "/export markdown chat.md".parse::<SlashCommand>()?,
+            SlashCommand::ExportMarkdown(Some(PathBuf::from("chat.md")))
+        );
+        assert_eq!(
+            "/export markdown".parse::<SlashCommand>()?,
+            SlashCommand::ExportMarkdown(None)
+        );
+        assert_eq!("/quit".parse::<SlashCommand>()?, SlashCommand::Quit);
+        assert!("/prompt".parse::<SlashCommand>().is_err());
+        assert!("/export pdf".parse::<SlashCommand>().is_err());
+        assert!("/unknown".parse::<SlashCommand>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_resolve_persona_with_variables() -> Result<(), Error> {
+        let cli = Cli::try_parse_from([
+            "uncle",
+            "chat",
+            "--persona",
+            "trump",
+            "--personas-dir",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/personas"),
+            "--var",
+            "topic=Ethereum",
+        ])
+        .map_err(|e| Error::Config(e.to_string()))?;
+        let Command::Chat(args) = cli.command else {
+            return Err(Error::Config("Expected chat command".to_string()));
+        };
+
+        let (prompt, few_shot) = args.persona.resolve()?.unwrap_or_default();
+        assert!(prompt.contains("about Ethereum"));
+        assert_eq!(few_shot.len(), 1);
+        assert!(Cli::try_parse_from(["uncle", "chat"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_time_as_rfc3339_or_milliseconds() {
+        assert_eq!(parse_time("1700000000000"), Ok(1_700_000_000_000));
+        assert_eq!(parse_time("2023-11-14T22:13:20Z"), Ok(1_700_000_000_000));
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{error::Error, language::LanguageDetector};
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, fs, path::Path, str::FromStr};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+const DEFAULT_PROMPT: &str = r#"Analyze the text sentiment and provide the result in JSON format like in this example:
+{"negative": 0.0, "neutral": 0.0, "positive": 0.0} where 0.0 is a real value given as a float.
+All the values are between 0.0 and 1.0 and sum up to 1.0.
+"#;
+
+const DEFAULT_LANGUAGES: [&str; 3] = ["english", "eng", "british"];
+
+const DEFAULT_DETECTED_LANGUAGES: [&str; 1] = ["eng"];
+
+const DEFAULT_MIN_LANGUAGE_CONFIDENCE: f64 = 0.5;
+
+const MAX_SIMHASH_DISTANCE: u32 = 64;
+
+const DEFAULT_ASSETS: [(&str, &[&str]); 12] = [
+    ("ADA", &["ada", "cardano"]),
+    ("BNB", &["bnb"]),
+    ("BTC", &["bitcoin", "bitcoins", "btc", "xbt"]),
+    ("DOGE", &["dogecoin", "doge"]),
+    ("ETH", &["ethereum", "ethereums", "eth"]),
+    ("NFT", &["nft", "nfts"]),
+    ("SOL", &["solana", "solanas", "sol"]),
+    ("STABLECOIN", &["stablecoin", "stablecoins"]),
+    ("STETH", &["steth"]),
+    ("USDC", &["usdc"]),
+    ("USDT", &["usdt"]),
+    ("XRP", &["xrp"]),
+];
+
+/// Configuration of the pull pipeline, the news source, the watched coins, the accepted languages and the schedule.
+/// Loaded from the TOML file, every field can be overridden with the `PULL_<FIELD>` environment variable,
+/// lists are given as comma separated values and assets as `BTC=bitcoin|btc,ETH=ethereum|eth`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters)]
+#[serde(default)]
+pub struct PullConfig {
+    /// Prompt scoring the sentiment of the article.
+    prompt: String,
+    /// Keywords or phrases the news are searched for.
+    keywords: String,
+    /// Languages of the news that are analyzed, matched against the language given by the provider.
+    languages: Vec<String>,
+    /// Languages detected from the article text, ISO 639-3 codes or English names, detection is off if empty.
+    detected_languages: Vec<String>,
+    /// Minimum confidence of the detected language between 0.0 and 1.0.
+    min_language_confidence: f64,
+    /// Aliases of the assets extracted from the news by the canonical asset id.
+    assets: BTreeMap<String, Vec<String>>,
+    /// Extracts the cashtags of the assets missing in `assets`, such as `$PEPE`, as the upper case symbols.
+    unknown_cashtags: bool,
+    /// Seconds between the pulls.
+    interval_s: u64,
+    /// Maximum number of pages fetched in a single pull.
+    max_pages: usize,
+    /// Number of requests the provider allows per day, the pull stops when they are spent.
+    daily_credits: u64,
+    /// Seconds to wait for a single provider response.
+    request_timeout_s: u64,
+    /// Number of the retries of the failed pull.
+    max_retries: usize,
+    /// Milliseconds before the first retry, doubled with every next retry.
+    backoff_base_ms: u64,
+    /// Maximum seconds between the retries, including the wait the provider asks for.
+    backoff_max_s: u64,
+    /// Number of the corrections asked from the model when the sentiment reply is invalid.
+    sentiment_retries: usize,
+    /// Hours the analyzed articles are remembered to skip their duplicates.
+    dedup_window_h: u64,
+    /// Maximum Hamming distance of the title and text SimHash of the near duplicate articles,
+    /// 0 skips only the articles with the same fingerprint.
+    max_simhash_distance: u32,
+}
+
+impl Default for PullConfig {
+    fn default() -> Self {
+        Self {
+            prompt: DEFAULT_PROMPT.to_string(),
+            keywords: "crypto".to_string(),
+            languages: DEFAULT_LANGUAGES.iter().map(|l| l.to_string()).collect(),
+            detected_languages: DEFAULT_DETECTED_LANGUAGES
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            min_language_confidence: DEFAULT_MIN_LANGUAGE_CONFIDENCE,
+            assets: DEFAULT_ASSETS
+                .iter()
+                .map(|(asset, aliases)| {
+                    let aliases = aliases.iter().map(|a| a.to_string()).collect();
+                    (asset.to_string(), aliases)
+                })
+                .collect(),
+            unknown_cashtags: false,
+            interval_s: SECONDS_PER_DAY / 200, // 200 queries per day
+            max_pages: 1,
+            daily_credits: 200,
+            request_timeout_s: 30,
+            max_retries: 3,
+            backoff_base_ms: 1000,
+            backoff_max_s: 60,
+            sentiment_retries: 2,
+            dedup_window_h: 48,
+            max_simhash_distance: 6,
+        }
+    }
+}
+
+impl PullConfig {
+    /// Loads the configuration from the file, applies the environment variable overrides and validates it.
+    ///
+    /// * `path` - Path to the TOML file, the defaults are used if not given.
+    ///
+    /// # Returns
+    ///
+    /// * Success with PullConfig or `Error::Config` if the file can not be read or the configuration is invalid.
+    pub fn try_load(path: Option<&Path>) -> Result<Self, Error> {
+        let mut config = match path {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+                toml::from_str(&content)
+                    .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?
+            }
+            None => Self::default(),
+        };
+        config.apply_overrides(|name| env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Overrides the fields with the `PULL_<FIELD>` variables.
+    ///
+    /// * `var` - Returns the value of the variable if it is set.
+    ///
+    /// # Returns
+    ///
+    /// * Success if all the set variables are parsed or `Error::Config` otherwise.
+    fn apply_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), Error> {
+        if let Some(prompt) = var("PULL_PROMPT") {
+            self.prompt = prompt;
+        }
+        if let Some(keywords) = var("PULL_KEYWORDS") {
+            self.keywords = keywords;
+        }
+        if let Some(languages) = var("PULL_LANGUAGES") {
+            self.languages = split_list(&languages);
+        }
+        if let Some(languages) = var("PULL_DETECTED_LANGUAGES") {
+            self.detected_languages = split_list(&languages);
+        }
+        if let Some(confidence) = var("PULL_MIN_LANGUAGE_CONFIDENCE") {
+            self.min_language_confidence = parse_var("PULL_MIN_LANGUAGE_CONFIDENCE", &confidence)?;
+        }
+        if let Some(assets) = var("PULL_ASSETS") {
+            self.assets = parse_assets(&assets)?;
+        }
+        if let Some(unknown) = var("PULL_UNKNOWN_CASHTAGS") {
+            self.unknown_cashtags = parse_var("PULL_UNKNOWN_CASHTAGS", &unknown)?;
+        }
+        if let Some(interval_s) = var("PULL_INTERVAL_S") {
+            self.interval_s = parse_var("PULL_INTERVAL_S", &interval_s)?;
+        }
+        if let Some(max_pages) = var("PULL_MAX_PAGES") {
+            self.max_pages = parse_var("PULL_MAX_PAGES", &max_pages)?;
+        }
+        if let Some(daily_credits) = var("PULL_DAILY_CREDITS") {
+            self.daily_credits = parse_var("PULL_DAILY_CREDITS", &daily_credits)?;
+        }
+        if let Some(timeout) = var("PULL_REQUEST_TIMEOUT_S") {
+            self.request_timeout_s = parse_var("PULL_REQUEST_TIMEOUT_S", &timeout)?;
+        }
+        if let Some(retries) = var("PULL_MAX_RETRIES") {
+            self.max_retries = parse_var("PULL_MAX_RETRIES", &retries)?;
+        }
+        if let Some(base) = var("PULL_BACKOFF_BASE_MS") {
+            self.backoff_base_ms = parse_var("PULL_BACKOFF_BASE_MS", &base)?;
+        }
+        if let Some(max) = var("PULL_BACKOFF_MAX_S") {
+            self.backoff_max_s = parse_var("PULL_BACKOFF_MAX_S", &max)?;
+        }
+        if let Some(retries) = var("PULL_SENTIMENT_RETRIES") {
+            self.sentiment_retries = parse_var("PULL_SENTIMENT_RETRIES", &retries)?;
+        }
+        if let Some(window) = var("PULL_DEDUP_WINDOW_H") {
+            self.dedup_window_h = parse_var("PULL_DEDUP_WINDOW_H", &window)?;
+        }
+        if let Some(distance) = var("PULL_MAX_SIMHASH_DISTANCE") {
+            self.max_simhash_distance = parse_var("PULL_MAX_SIMHASH_DISTANCE", &distance)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the configuration.
+    ///
+    /// # Returns
+    ///
+    /// * Success if the configuration is valid or `Error::Config` describing the invalid field.
+    fn validate(&self) -> Result<(), Error> {
+        if self.prompt.trim().is_empty() {
+            return Err(Error::Config("Pull prompt is empty".to_string()));
+        }
+        if self.languages.is_empty() {
+            return Err(Error::Config("Pull languages are empty".to_string()));
+        }
+        if self.assets.is_empty() || self.assets.values().any(|a| a.is_empty()) {
+            return Err(Error::Config(
+                "Pull assets are empty or have no aliases".to_string(),
+            ));
+        }
+        self.language_detector()?;
+        if self.interval_s == 0 || self.max_pages == 0 {
+            return Err(Error::Config(
+                "Pull interval_s and max_pages must be greater than 0".to_string(),
+            ));
+        }
+        if self.request_timeout_s == 0 {
+            return Err(Error::Config(
+                "Pull request_timeout_s must be greater than 0".to_string(),
+            ));
+        }
+        if self.max_simhash_distance > MAX_SIMHASH_DISTANCE {
+            return Err(Error::Config(format!(
+                "Pull max_simhash_distance [ {} ] is greater than {MAX_SIMHASH_DISTANCE}",
+                self.max_simhash_distance
+            )));
+        }
+        let daily_requests = SECONDS_PER_DAY.div_ceil(self.interval_s) * self.max_pages as u64;
+        if daily_requests > self.daily_credits {
+            return Err(Error::Config(format!(
+                "Pull schedule needs up to [ {daily_requests} ] requests per day, exceeding [ {} ] daily credits",
+                self.daily_credits
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl PullConfig {
+    /// Language detector of the article text.
+    ///
+    /// # Returns
+    ///
+    /// * Success with LanguageDetector, `None` if the detection is off, or `Error::Config` if the languages are invalid.
+    pub fn language_detector(&self) -> Result<Option<LanguageDetector>, Error> {
+        if self.detected_languages.is_empty() {
+            return Ok(None);
+        }
+        LanguageDetector::try_new(&self.detected_languages, self.min_language_confidence).map(Some)
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_assets(value: &str) -> Result<BTreeMap<String, Vec<String>>, Error> {
+    split_list(value)
+        .iter()
+        .map(|asset| match asset.split_once('=') {
+            Some((asset, aliases)) => Ok((
+                asset.trim().to_string(),
+                aliases
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )),
+            None => Err(Error::Config(format!(
+                "PULL_ASSETS [ {asset} ] is not in the ASSET=alias|alias format"
+            ))),
+        })
+        .collect()
+}
+
+fn parse_var<T: FromStr>(name: &str, value: &str) -> Result<T, Error>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| Error::Config(format!("{name} [ {value} ]: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_should_load_config_file_with_defaults_for_missing_fields() -> Result<(), Error> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("pull.toml");
+        let config = PullConfig::try_load(Some(&path))?;
+        assert_eq!(config, PullConfig::default());
+
+        let config: PullConfig =
+            toml::from_str("interval_s = 600\n[assets]\nBTC = [\"bitcoin\", \"btc\"]")
+                .map_err(|e| Error::Config(e.to_string()))?;
+        assert_eq!(
+            config.assets(),
+            &BTreeMap::from([(
+                "BTC".to_string(),
+                vec!["bitcoin".to_string(), "btc".to_string()]
+            )])
+        );
+        assert_eq!(*config.interval_s(), 600);
+        assert_eq!(config.languages(), PullConfig::default().languages());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_apply_environment_overrides() -> Result<(), Error> {
+        let vars = HashMap::from([
+            ("PULL_ASSETS", "BTC=bitcoin|btc, ETH=eth,,"),
+            ("PULL_LANGUAGES", "english, eng,,"),
+            ("PULL_INTERVAL_S", "3600"),
+            ("PULL_UNKNOWN_CASHTAGS", "true"),
+        ]);
+        let mut config = PullConfig::default();
+        config.apply_overrides(|name| vars.get(name).map(|v| v.to_string()))?;
+
+        assert_eq!(
+            config.assets(),
+            &BTreeMap::from([
+                (
+                    "BTC".to_string(),
+                    vec!["bitcoin".to_string(), "btc".to_string()]
+                ),
+                ("ETH".to_string(), vec!["eth".to_string()]),
+            ])
+        );
+        assert_eq!(config.languages(), &vec!["english", "eng"]);
+        assert_eq!(*config.interval_s(), 3600);
+        assert!(*config.unknown_cashtags());
+        assert!(config
+            .apply_overrides(|name| (name == "PULL_MAX_PAGES").then(|| "many".to_string()))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_schedule_exceeding_daily_credits() {
+        let mut config = PullConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.max_pages = 2;
+        assert!(config.validate().is_err());
+
+        config.max_pages = 1;
+        config.detected_languages = vec!["klingon".to_string()];
+        assert!(config.validate().is_err());
+
+        config.detected_languages.clear();
+        assert!(config.validate().is_ok());
+        config.max_simhash_distance = 65;
+        assert!(config.validate().is_err());
+        config.max_simhash_distance = 6;
+        config.assets.clear();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use crate::{
+    error::Error,
+    shared::{NewsResponse, SentimentData},
+    traits::Store,
+};
+use std::{collections::HashMap, fmt::Display};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+const SHINGLE_WORDS: usize = 2;
+
+/// Reason the article is skipped before the sentiment analysis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Duplicate {
+    /// The provider flagged the article as a duplicate.
+    Provider,
+    /// The article with the same id was already analyzed.
+    Seen,
+    /// The article is a near duplicate of the analyzed article with the given id.
+    Similar(String),
+}
+
+impl Display for Duplicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Provider => write!(f, "flagged as duplicate by the provider"),
+            Self::Seen => write!(f, "already analyzed"),
+            Self::Similar(id) => write!(f, "near duplicate of [ {id} ]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Fingerprint {
+    id: String,
+    remembered_at: u64,
+    hash: u64,
+}
+
+/// Skips the articles that would waste the model time: the provider duplicates,
+/// the articles already analyzed and the near duplicates such as syndicated copies of the same story.
+/// Near duplicates are detected by the Hamming distance of the title and text SimHash.
+/// The short text is hashed rather than the full body, because the body of every copy carries
+/// the source boilerplate such as bylines and footers, and the data stored before the body was kept has no body.
+#[derive(Debug, Clone)]
+pub struct Deduplicator {
+    window_ms: u64,
+    max_distance: u32,
+    seen: HashMap<String, u64>,
+    fingerprints: Vec<Fingerprint>,
+}
+
+impl Deduplicator {
+    /// Creates the empty Deduplicator.
+    ///
+    /// * `window_ms` - Articles remembered longer than the window before the latest ingestion are forgotten.
+    /// * `max_distance` - Maximum Hamming distance of the near duplicate SimHash.
+    pub fn new(window_ms: u64, max_distance: u32) -> Self {
+        Self {
+            window_ms,
+            max_distance,
+            seen: HashMap::new(),
+            fingerprints: Vec::new(),
+        }
+    }
+
+    /// Creates the Deduplicator remembering the articles ingested within the window.
+    /// The data stored before the ingestion time was kept is remembered by the publication time.
+    ///
+    /// * `store` - Storage of the analyzed articles.
+    /// * `now_ms` - Current time in [ ms ] since the epoch.
+    /// * `window_ms` - Articles ingested longer than the window ago are forgotten.
+    /// * `max_distance` - Maximum Hamming distance of the near duplicate SimHash.
+    ///
+    /// # Returns
+    ///
+    /// * Success with Deduplicator or `Error` if the storage can not be read.
+    pub async fn try_from_store<S>(
+        store: &S,
+        now_ms: u64,
+        window_ms: u64,
+        max_distance: u32,
+    ) -> Result<Self, Error>
+    where
+        S: for<'a> Store<'a, SentimentData>,
+    {
+        let mut deduplicator = Self::new(window_ms, max_distance);
+        let stored = store
+            .read_ingested_from_time(now_ms.saturating_sub(window_ms) as u128)
+            .await?;
+        for data in stored.iter() {
+            deduplicator.insert(
+                data.resource_id(),
+                (*data.ingested_at()).max(*data.created_at()),
+                simhash(&format!("{}\n{}", data.title(), data.text())),
+            );
+        }
+
+        Ok(deduplicator)
+    }
+
+    /// Checks if the article is a duplicate.
+    ///
+    /// # Returns
+    ///
+    /// * Duplicate reason or `None` if the article should be analyzed.
+    pub fn check(&self, news: &NewsResponse) -> Option<Duplicate> {
+        if *news.duplicate() {
+            return Some(Duplicate::Provider);
+        }
+        if self.seen.contains_key(news.id()) {
+            return Some(Duplicate::Seen);
+        }
+        let hash = simhash(&format!("{}\n{}", news.title(), news.text()));
+        self.fingerprints
+            .iter()
+            .find(|f| (f.hash ^ hash).count_ones() <= self.max_distance)
+            .map(|f| Duplicate::Similar(f.id.clone()))
+    }
+
+    /// Remembers the analyzed article at its ingestion time and forgets the articles remembered outside of the window.
+    /// The publication time is not used, so the article published long before it is pulled is still remembered.
+    pub fn remember(&mut self, news: &NewsResponse) {
+        self.insert(
+            news.id(),
+            *news.ingested_at(),
+            simhash(&format!("{}\n{}", news.title(), news.text())),
+        );
+        let oldest = news.ingested_at().saturating_sub(self.window_ms);
+        self.seen
+            .retain(|_, remembered_at| *remembered_at >= oldest);
+        self.fingerprints.retain(|f| f.remembered_at >= oldest);
+    }
+
+    fn insert(&mut self, id: &str, remembered_at: u64, hash: u64) {
+        self.seen.insert(id.to_string(), remembered_at);
+        self.fingerprints.push(Fingerprint {
+            id: id.to_string(),
+            remembered_at,
+            hash,
+        });
+    }
+}
+
+/// 64 bit SimHash of the lowercase word shingles of the text.
+///
+/// # Returns
+///
+/// * Fingerprint where similar texts differ in few bits, 0 for the text without words.
+pub fn simhash(text: &str) -> u64 {
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let shingles: Vec<String> = if words.len() < SHINGLE_WORDS {
+        words.iter().map(|w| w.to_string()).collect()
+    } else {
+        words.windows(SHINGLE_WORDS).map(|w| w.join(" ")).collect()
+    };
+
+    let mut weights = [0_i64; 64];
+    for shingle in shingles.iter() {
+        let hash = mix(fnv1a(shingle.as_bytes()));
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) == 0 {
+                *weight -= 1;
+            } else {
+                *weight += 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | (1 << bit))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// MurmurHash3 finalizer spreading the FNV bits evenly, every bit of the SimHash needs an unbiased vote.
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{NewsResponseBuilder, SentimentDataBuilder, SentimentResultBuilder};
+
+    const STORY: &str = "Bitcoin climbs above 70,000 dollars as spot ETF inflows reach a record \
+        while traders expect the Federal Reserve to cut interest rates later this year";
+
+    fn news(id: &str, title: &str, text: &str, created_at: u64, duplicate: bool) -> NewsResponse {
+        NewsResponseBuilder::default()
+            .id(id.to_string())
+            .title(title.to_string())
+            .origin("origin".to_string())
+            .text(text.to_string())
+            .link("link".to_string())
+            .created_at(created_at)
+            .ingested_at(created_at)
+            .coins(vec![])
+            .keywords(vec![])
+            .duplicate(duplicate)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn it_should_give_close_hashes_to_near_duplicates() {
+        let copy = format!("{STORY}, reports say.");
+        let other = "Ethereum developers schedule the next network upgrade for the spring \
+            after the final test network fork completed without issues";
+
+        assert!((simhash(STORY) ^ simhash(&copy)).count_ones() <= 6);
+        assert!((simhash(STORY) ^ simhash(other)).count_ones() > 6);
+        assert_eq!(simhash(STORY), simhash(&STORY.to_uppercase()));
+        assert_eq!(simhash(""), 0);
+    }
+
+    #[test]
+    fn it_should_skip_provider_seen_and_similar_articles() {
+        let mut deduplicator = Deduplicator::new(1_000, 6);
+        let first = news("1", "Bitcoin record", STORY, 10_000, false);
+        assert_eq!(deduplicator.check(&first), None);
+        deduplicator.remember(&first);
+
+        assert_eq!(deduplicator.check(&first), Some(Duplicate::Seen));
+        assert_eq!(
+            deduplicator.check(&news("2", "Bitcoin record", STORY, 10_000, false)),
+            Some(Duplicate::Similar("1".to_string()))
+        );
+        assert_eq!(
+            deduplicator.check(&news("3", "Other", "Unrelated story", 10_000, true)),
+            Some(Duplicate::Provider)
+        );
+
+        let late = news(
+            "4",
+            "Ethereum upgrade",
+            "Developers plan a fork.",
+            20_000,
+            false,
+        );
+        assert_eq!(deduplicator.check(&late), None);
+        deduplicator.remember(&late);
+        assert_eq!(deduplicator.check(&first), None);
+    }
+
+    struct MemoryStore {
+        data: Vec<SentimentData>,
+    }
+
+    impl<'a> Store<'a, SentimentData> for MemoryStore {
+        async fn save(&self, _entity: &SentimentData) -> Result<String, Error> {
+            Err(Error::Storage("Read only".to_string()))
+        }
+
+        async fn read_by_id(&self, id: &str) -> Result<SentimentData, Error> {
+            Err(Error::NotFound(id.to_string()))
+        }
+
+        async fn read_from_time(&self, timestamp_ms: u128) -> Result<Vec<SentimentData>, Error> {
+            Ok(self
+                .data
+                .iter()
+                .filter(|d| *d.created_at() as u128 >= timestamp_ms)
+                .cloned()
+                .collect())
+        }
+
+        async fn read_ingested_from_time(
+            &self,
+            timestamp_ms: u128,
+        ) -> Result<Vec<SentimentData>, Error> {
+            Ok(self
+                .data
+                .iter()
+                .filter(|d| (*d.ingested_at()).max(*d.created_at()) as u128 >= timestamp_ms)
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_reload_old_article_ingested_within_window() -> Result<(), Error> {
+        let stored = SentimentDataBuilder::default()
+            .resource_id("1".to_string())
+            .title("Bitcoin record".to_string())
+            .origin("origin".to_string())
+            .text(STORY.to_string())
+            .link("link".to_string())
+            .created_at(1_000)
+            .ingested_at(50_000)
+            .coins(vec![])
+            .keywords(vec![])
+            .sentiment(
+                SentimentResultBuilder::default()
+                    .negative(0.0)
+                    .neutral(1.0)
+                    .positive(0.0)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let store = MemoryStore { data: vec![stored] };
+
+        let deduplicator = Deduplicator::try_from_store(&store, 50_500, 1_000, 6).await?;
+
+        assert_eq!(
+            deduplicator.check(&news("1", "Bitcoin record", STORY, 1_000, false)),
+            Some(Duplicate::Seen)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_remember_old_article_for_the_window_after_ingestion() {
+        let mut deduplicator = Deduplicator::new(1_000, 6);
+        let old = NewsResponseBuilder::default()
+            .id("1".to_string())
+            .title("Bitcoin record".to_string())
+            .origin("origin".to_string())
+            .text(STORY.to_string())
+            .link("link".to_string())
+            .created_at(1_000)
+            .ingested_at(50_000)
+            .coins(vec![])
+            .keywords(vec![])
+            .duplicate(false)
+            .build()
+            .unwrap();
+        deduplicator.remember(&old);
+
+        assert_eq!(deduplicator.check(&old), Some(Duplicate::Seen));
+        assert_eq!(
+            deduplicator.check(&news("2", "Bitcoin record", STORY, 50_500, false)),
+            Some(Duplicate::Similar("1".to_string()))
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fmt::Display, time::Duration};
+
+/// Error of the uncle_good_advice library.
+/// Variants distinguish the failure source so the caller can decide whether to retry or give up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Transient HTTP failure such as a timeout, a connection reset or a 5xx response.
+    Http(String),
+    /// Provider rejected the request because of the rate limit, or the daily credit budget is spent.
+    /// Holds the time to wait before the next request if it is known.
+    RateLimited(String, Option<Duration>),
+    /// Request rejected because of the invalid or expired API key.
+    Unauthorized(String),
+    /// Provider responded with the malformed body or reported the failure in the body.
+    Provider(String),
+    /// Storage is not reachable or failed to process the operation.
+    Storage(String),
+    /// Requested entity does not exist.
+    NotFound(String),
+    /// Model could not be loaded.
+    ModelLoad(String),
+    /// Model failed while generating the response.
+    Generation(String),
+    /// Model replied with the content that can not be used.
+    InvalidReply(String),
+    /// Configuration is missing or invalid, including the request the provider rejected with the 4xx status.
+    Config(String),
+    /// Program was interrupted by the user.
+    Interrupted,
+}
+
+impl Error {
+    /// Tells if the failure is transient and the operation may succeed when retried later.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if operation can be retried or `false` otherwise.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Http(_)
+                | Self::RateLimited(_, _)
+                | Self::Provider(_)
+                | Self::Storage(_)
+                | Self::Generation(_)
+                | Self::InvalidReply(_)
+        )
+    }
+
+    /// Classifies the failed HTTP response status.
+    /// The rejected request such as the invalid query is not transient, retrying it would fail the same way.
+    ///
+    /// * `status` - HTTP status code of the response.
+    /// * `message` - Description of the failure.
+    ///
+    /// # Returns
+    ///
+    /// * Error variant matching the status.
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            401 | 403 => Self::Unauthorized(message),
+            429 => Self::RateLimited(message, None),
+            400..=499 => Self::Config(message),
+            _ => Self::Http(message),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(e) => write!(f, "HTTP failure: {e}"),
+            Self::RateLimited(e, Some(after)) => {
+                write!(f, "Rate limited: {e}, retry after {}s", after.as_secs())
+            }
+            Self::RateLimited(e, None) => write!(f, "Rate limited: {e}"),
+            Self::Unauthorized(e) => write!(f, "Unauthorized: {e}"),
+            Self::Provider(e) => write!(f, "Provider failure: {e}"),
+            Self::Storage(e) => write!(f, "Storage failure: {e}"),
+            Self::NotFound(e) => write!(f, "Not found: {e}"),
+            Self::ModelLoad(e) => write!(f, "Model load failure: {e}"),
+            Self::Generation(e) => write!(f, "Generation failure: {e}"),
+            Self::InvalidReply(e) => write!(f, "Invalid model reply: {e}"),
+            Self::Config(e) => write!(f, "Invalid configuration: {e}"),
+            Self::Interrupted => write!(f, "Program was interrupted..."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        let e = e.without_url();
+        match e.status() {
+            Some(status) => Self::from_status(status.as_u16(), e.to_string()),
+            None if e.is_decode() => Self::Provider(e.to_string()),
+            None if e.is_builder() => Self::Config(e.to_string()),
+            None => Self::Http(e.to_string()),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for Error {
+    fn from(e: mongodb::error::Error) -> Self {
+        Self::Storage(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_classify_transient_errors() {
+        assert!(Error::Http("timeout".to_string()).is_transient());
+        assert!(Error::Storage("connection refused".to_string()).is_transient());
+        assert!(Error::RateLimited("429".to_string(), None).is_transient());
+        assert!(Error::Generation("stream closed".to_string()).is_transient());
+        assert!(!Error::Unauthorized("invalid key".to_string()).is_transient());
+        assert!(!Error::ModelLoad("missing weights".to_string()).is_transient());
+        assert!(!Error::Config("missing key".to_string()).is_transient());
+        assert!(!Error::Interrupted.is_transient());
+        assert!(!Error::from_status(400, "invalid query".to_string()).is_transient());
+        assert!(!Error::from_status(422, "unsupported parameter".to_string()).is_transient());
+        assert!(!Error::from_status(401, "invalid key".to_string()).is_transient());
+        assert!(Error::from_status(429, "too many requests".to_string()).is_transient());
+        assert!(Error::from_status(502, "bad gateway".to_string()).is_transient());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{error::Error, traits::ValidatorStrategy};
+use whatlang::Lang;
+
+/// Validates the text language detected offline with the trigram model, independently of the text source.
+/// The text is valid if it is written in one of the accepted languages with at least the minimum confidence.
+#[derive(Debug, Clone)]
+pub struct LanguageDetector {
+    languages: Vec<Lang>,
+    min_confidence: f64,
+}
+
+impl LanguageDetector {
+    /// Creates the detector accepting the given languages.
+    ///
+    /// * `languages` - ISO 639-3 codes such as `eng` or English names such as `English`.
+    /// * `min_confidence` - Minimum detection confidence between 0.0 and 1.0.
+    ///
+    /// # Returns
+    ///
+    /// * Success with LanguageDetector or `Error::Config` if the language is unknown or the confidence is out of range.
+    pub fn try_new(languages: &[String], min_confidence: f64) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&min_confidence) {
+            return Err(Error::Config(format!(
+                "Language confidence [ {min_confidence} ] is not between 0.0 and 1.0"
+            )));
+        }
+        let languages = languages
+            .iter()
+            .map(|l| parse_lang(l))
+            .collect::<Result<Vec<Lang>, Error>>()?;
+
+        Ok(Self {
+            languages,
+            min_confidence,
+        })
+    }
+}
+
+fn parse_lang(language: &str) -> Result<Lang, Error> {
+    let language = language.trim().to_lowercase();
+    Lang::from_code(language.as_str())
+        .or_else(|| {
+            Lang::all()
+                .iter()
+                .find(|l| l.eng_name().to_lowercase() == language)
+                .copied()
+        })
+        .ok_or(Error::Config(format!("Unknown language [ {language} ]")))
+}
+
+impl ValidatorStrategy<String> for LanguageDetector {
+    fn is_valid(&self, value: &String) -> bool {
+        whatlang::detect(value).is_some_and(|info| {
+            self.languages.contains(&info.lang()) && info.confidence() >= self.min_confidence
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_accept_text_in_detected_language() -> Result<(), Error> {
+        let detector = LanguageDetector::try_new(&["eng".to_string()], 0.5)?;
+
+        assert!(detector.is_valid(
+            &"The Securities and Exchange Commission approved several spot crypto funds on Thursday."
+                .to_string()
+        ));
+        assert!(!detector.is_valid(
+            &"Bitcoin sube por encima de su récord anterior mientras los inversores invierten en fondos cotizados."
+                .to_string()
+        ));
+        assert!(!detector.is_valid(&"BTC ETH SOL".to_string()));
+        assert!(!detector.is_valid(&"".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_language_codes_and_names() -> Result<(), Error> {
+        let detector = LanguageDetector::try_new(&["deu".to_string(), "Spanish".to_string()], 0.0)?;
+        assert_eq!(detector.languages, vec![Lang::Deu, Lang::Spa]);
+
+        assert!(LanguageDetector::try_new(&["british".to_string()], 0.5).is_err());
+        assert!(LanguageDetector::try_new(&["eng".to_string()], 1.5).is_err());
+
+        Ok(())
+    }
+}
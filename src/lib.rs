@@ -1,6 +1,16 @@
 pub mod advise;
 pub mod cli;
+pub mod config;
+pub mod dedup;
+pub mod error;
+pub mod language;
 pub mod mongostore;
 pub mod newsdata_io;
+pub mod persona;
+#[cfg(feature = "server")]
+pub mod push;
+pub mod report;
+pub mod retry;
+pub mod sentiment;
 pub mod shared;
 pub mod traits;
@@ -1,13 +1,15 @@
-use crate::{shared::SentimentData, traits::Store};
+use crate::{error::Error, shared::SentimentData, traits::Store};
 use derive_builder::Builder;
 use kalosm::language::StreamExt;
 use mongodb::{
-    bson::{self, doc, oid::ObjectId},
+    bson::{doc, oid::ObjectId},
     Client, Database,
 };
-use std::fmt::Debug;
+use std::{env, fmt::Debug};
 
 const COLLECTION_NAME: &str = "sentiment_reports";
+const DEFAULT_URL_WITH_CREDENTIALS: &str = "localhost:27017";
+const DEFAULT_DATABASE: &str = "uncle_good_advice";
 
 #[derive(Debug, Builder)]
 pub struct Uri {
@@ -31,23 +33,39 @@ pub struct Storage {
 }
 
 impl Storage {
-    pub async fn try_new(uri: impl Into<String>, db_name: &str) -> mongodb::error::Result<Self> {
+    pub async fn try_new(uri: impl Into<String>, db_name: &str) -> Result<Self, Error> {
         let client = Client::with_uri_str(uri.into()).await?;
 
         Ok(Self {
             db: client.database(db_name),
         })
     }
+
+    /// Creates the storage from `MONGO_URL_WITH_CREDENTIALS`, `MONGO_DATABASE` and `MONGO_TLS` environment variables.
+    /// Missing variables default to the local development database from docker-compose.
+    pub async fn try_from_env() -> Result<Self, Error> {
+        let database = env::var("MONGO_DATABASE").unwrap_or(DEFAULT_DATABASE.to_string());
+        let uri = UriBuilder::default()
+            .url_with_credentials(
+                env::var("MONGO_URL_WITH_CREDENTIALS")
+                    .unwrap_or(DEFAULT_URL_WITH_CREDENTIALS.to_string()),
+            )
+            .database(database.clone())
+            .tls(env::var("MONGO_TLS").is_ok_and(|tls| tls == "true"))
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        Self::try_new(uri, &database).await
+    }
 }
 
 impl<'a> Store<'a, SentimentData> for Storage {
-    async fn save(&self, entity: &SentimentData) -> Result<String, String> {
+    async fn save(&self, entity: &SentimentData) -> Result<String, Error> {
         let result = self
             .db
             .collection::<SentimentData>(COLLECTION_NAME)
             .insert_one(entity)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         Ok(result
             .inserted_id
@@ -56,27 +74,49 @@ impl<'a> Store<'a, SentimentData> for Storage {
             .to_hex())
     }
 
-    async fn read_by_id(&self, id: &str) -> Result<SentimentData, String> {
-        let obj_id = ObjectId::parse_str(id).map_err(|e| e.to_string())?;
-        let result = self
-            .db
+    async fn read_by_id(&self, id: &str) -> Result<SentimentData, Error> {
+        let obj_id = ObjectId::parse_str(id).map_err(|e| Error::NotFound(format!("{id}: {e}")))?;
+        self.db
             .collection::<SentimentData>(COLLECTION_NAME)
             .find_one(doc! { "_id": obj_id })
-            .await
-            .map_err(|e| e.to_string())?
-            .unwrap_or_default();
+            .await?
+            .ok_or(Error::NotFound(id.to_string()))
+    }
+
+    async fn read_from_time(&self, timestamp_ms: u128) -> Result<Vec<SentimentData>, Error> {
+        let query = doc! { "created_at": { "$gte": timestamp_ms as i64 } };
+
+        let mut cursor = self
+            .db
+            .collection::<SentimentData>(COLLECTION_NAME)
+            .find(query)
+            .await?;
+
+        let mut result = Vec::new();
+        while let Some(d) = cursor.next().await {
+            if let Ok(entity) = d {
+                result.push(entity);
+            }
+        }
+
         Ok(result)
     }
 
-    async fn read_from_time(&self, timestamp_ms: u128) -> Result<Vec<SentimentData>, String> {
-        let query = doc! { "created_at": { "$gte": bson::Decimal128::from_bytes(timestamp_ms.to_be_bytes())}};
+    async fn read_ingested_from_time(
+        &self,
+        timestamp_ms: u128,
+    ) -> Result<Vec<SentimentData>, Error> {
+        // The data stored before the ingestion time was kept has only the creation time.
+        let query = doc! { "$or": [
+            { "ingested_at": { "$gte": timestamp_ms as i64 } },
+            { "created_at": { "$gte": timestamp_ms as i64 } },
+        ] };
 
         let mut cursor = self
             .db
             .collection::<SentimentData>(COLLECTION_NAME)
             .find(query)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         let mut result = Vec::new();
         while let Some(d) = cursor.next().await {
@@ -107,7 +147,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn it_should_validate_store_implementation() -> Result<(), String> {
+    async fn it_should_validate_store_implementation() -> Result<(), crate::error::Error> {
         use crate::shared::{SentimentDataBuilder, SentimentResultBuilder};
         use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -140,6 +180,7 @@ mod tests {
             .text("This message is faked for test porpuses".to_string())
             .link("https://google.com".to_string())
             .created_at(now as u64)
+            .ingested_at(now as u64)
             .coins(vec![])
             .keywords(vec![])
             .sentiment(result)
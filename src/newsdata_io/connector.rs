@@ -1,19 +1,114 @@
-use super::{extractor::CoinExtractor, validator::NewsDataIoLanguageValidator};
+use super::query::NewsDataIoQuery;
 use crate::{
-    shared::{NewsResponse, NewsResponseBuilder},
+    error::Error,
+    retry::retry_after,
+    shared::{
+        NewsResponse, NewsResponseBuilder, NewsSourceBuilder, ProviderSentiment,
+        ProviderSentimentBuilder, ProviderTagsBuilder, SentimentLabel, SentimentResult,
+        SentimentResultBuilder,
+    },
     traits::{ExtractionStrategy, Fetcher, ValidatorStrategy},
 };
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use derive_builder::Builder;
-use reqwest;
+use reqwest::{self, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Borrow,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 const ORIGIN_URL: &str = "https://newsdata.io";
-const NEWSDATAIO_URL: &str = "https://newsdata.io/api/1/latest?apikey=";
+const NEWSDATAIO_URL: &str = "https://newsdata.io/api/1";
+const API_KEY_HEADER: &str = "X-ACCESS-KEY";
+const PUB_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 const SUCCESS: &str = "success";
+const DEFAULT_MAX_PAGES: usize = 1;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Placeholder NewsData.io sends instead of the fields that are not available in the free plan.
+const PAID_PLANS_ONLY: &str = "ONLY AVAILABLE IN PAID PLANS";
+
+/// NewsData.io field that is sent either as a single string or as a list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    /// Values of the field without the paid plans placeholder, the single string is split on commas.
+    fn into_values(self) -> Vec<String> {
+        let values = match self {
+            Self::One(value) => value.split(',').map(str::to_string).collect(),
+            Self::Many(values) => values,
+        };
+        values
+            .into_iter()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty() && v != PAID_PLANS_ONLY)
+            .collect()
+    }
+}
+
+/// NewsData.io sentiment scores in percents, or the paid plans placeholder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SentimentStats {
+    Scores {
+        negative: f64,
+        neutral: f64,
+        positive: f64,
+    },
+    Unavailable(String),
+}
+
+impl SentimentStats {
+    /// Scores normalized to sum up to 1.0, `None` if not available.
+    fn into_scores(self) -> Option<SentimentResult> {
+        let Self::Scores {
+            negative,
+            neutral,
+            positive,
+        } = self
+        else {
+            return None;
+        };
+        let total = negative + neutral + positive;
+        if total <= 0.0 {
+            return None;
+        }
+        SentimentResultBuilder::default()
+            .negative(negative / total)
+            .neutral(neutral / total)
+            .positive(positive / total)
+            .build()
+            .ok()
+    }
+}
+
+/// Sentiment given by the provider, `None` if the label is not available.
+fn provider_sentiment(
+    label: Option<String>,
+    stats: Option<SentimentStats>,
+) -> Result<Option<ProviderSentiment>, Error> {
+    let Some(label) = available(label).and_then(|l| l.parse::<SentimentLabel>().ok()) else {
+        return Ok(None);
+    };
+    ProviderSentimentBuilder::default()
+        .label(label)
+        .scores(stats.and_then(SentimentStats::into_scores))
+        .build()
+        .map(Some)
+        .map_err(|e| Error::Provider(e.to_string()))
+}
+
+/// Drops the paid plans placeholder and the empty value.
+fn available(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty() && v != PAID_PLANS_ONLY)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct NewsDataIoArticle {
@@ -26,8 +121,8 @@ struct NewsDataIoArticle {
     description: Option<String>,
     content: Option<String>,
     #[serde(rename = "pubDate")]
-    pub_data: Option<String>,
-    #[serde(rename = "pubDataTZ")]
+    pub_date: Option<String>,
+    #[serde(rename = "pubDateTZ")]
     pub_date_tz: Option<String>,
     image_url: Option<String>,
     source_id: Option<String>,
@@ -38,46 +133,139 @@ struct NewsDataIoArticle {
     language: Option<String>,
     country: Option<Vec<String>>,
     category: Option<Vec<String>>,
-    ai_tag: Option<String>,
-    ai_org: Option<String>,
-    ai_region: Option<String>,
+    ai_tag: Option<OneOrMany>,
+    ai_org: Option<OneOrMany>,
+    ai_region: Option<OneOrMany>,
     duplicate: bool,
     sentiment: Option<String>,
-    sentiment_stats: Option<String>,
+    sentiment_stats: Option<SentimentStats>,
     coins: Option<Vec<String>>,
 }
 
 impl TryInto<NewsResponse> for NewsDataIoArticle {
-    type Error = String;
+    type Error = Error;
 
     fn try_into(self) -> Result<NewsResponse, Self::Error> {
-        let now = SystemTime::now();
+        let ingested_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::Config(e.to_string()))?
+            .as_millis() as u64;
+        // The article with the malformed date is kept, dated at the ingestion like the article without the date.
+        let created_at = self
+            .pub_date
+            .as_deref()
+            .and_then(|date| parse_pub_date(date, self.pub_date_tz.as_deref()).ok())
+            .unwrap_or(ingested_at);
+        let values =
+            |field: Option<OneOrMany>| field.map(OneOrMany::into_values).unwrap_or_default();
+        let source = NewsSourceBuilder::default()
+            .id(self.source_id.unwrap_or_default())
+            .name(self.source_name.unwrap_or_default())
+            .url(self.source_url.unwrap_or_default())
+            .icon(self.source_icon.unwrap_or_default())
+            .priority(self.source_priority)
+            .build()
+            .map_err(|e| Error::Provider(e.to_string()))?;
+        let provider_tags = ProviderTagsBuilder::default()
+            .topics(values(self.ai_tag))
+            .organizations(values(self.ai_org))
+            .regions(values(self.ai_region))
+            .build()
+            .map_err(|e| Error::Provider(e.to_string()))?;
+        let provider_sentiment = provider_sentiment(self.sentiment, self.sentiment_stats)?;
+        let text = self.description.unwrap_or_default();
+        let body = available(self.content).unwrap_or(text.clone());
+
         NewsResponseBuilder::default()
             .id(self.article_id)
             .title(self.title.unwrap_or_default())
             .origin(ORIGIN_URL.to_string())
-            .text(self.description.unwrap_or_default())
+            .text(text)
+            .body(body)
             .link(self.link.unwrap_or_default())
-            .created_at(
-                now.duration_since(UNIX_EPOCH)
-                    .map_err(|e| format!("{e}"))?
-                    .as_millis() as u64,
-            )
+            .created_at(created_at)
+            .ingested_at(ingested_at)
             .coins(self.coins.unwrap_or_default())
             .keywords(self.keywords.unwrap_or_default())
+            .authors(self.creator.unwrap_or_default())
+            .categories(self.category.unwrap_or_default())
+            .countries(self.country.unwrap_or_default())
+            .image_url(available(self.image_url))
+            .source(source)
+            .provider_tags(provider_tags)
+            .provider_sentiment(provider_sentiment)
+            .duplicate(self.duplicate)
             .build()
-            .map_err(|e| format!("{e}"))
+            .map_err(|e| Error::Provider(e.to_string()))
     }
 }
 
+/// Parses the NewsData.io publication date given in the provider timezone.
+/// Timezone can be an IANA name such as `Asia/Kolkata` or a fixed offset such as `+05:30`, UTC is assumed when missing.
+///
+/// # Returns
+///
+/// * Success `u64` publication time in [ ms ] since the epoch or `Error::Provider` with message about failure.
+fn parse_pub_date(date: &str, tz: Option<&str>) -> Result<u64, Error> {
+    let naive = NaiveDateTime::parse_from_str(date.trim(), PUB_DATE_FORMAT)
+        .map_err(|e| Error::Provider(format!("Invalid pubDate [ {date} ]: {e}")))?;
+
+    let tz = tz.map(str::trim).unwrap_or_default();
+    let time: DateTime<Utc> = if tz.is_empty() {
+        naive.and_utc()
+    } else if let Ok(zone) = tz.parse::<Tz>() {
+        zone.from_local_datetime(&naive)
+            .earliest()
+            .ok_or(Error::Provider(format!(
+                "Nonexistent pubDate [ {date} ] in timezone [ {tz} ]"
+            )))?
+            .with_timezone(&Utc)
+    } else if let Ok(offset) = tz.parse::<FixedOffset>() {
+        offset
+            .from_local_datetime(&naive)
+            .single()
+            .ok_or(Error::Provider(format!(
+                "Invalid pubDate [ {date} ] with offset [ {tz} ]"
+            )))?
+            .with_timezone(&Utc)
+    } else {
+        return Err(Error::Provider(format!("Unknown pubDateTZ [ {tz} ]")));
+    };
+
+    u64::try_from(time.timestamp_millis()).map_err(|e| Error::Provider(e.to_string()))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct NewsDataIoResponse {
     status: String,
     #[serde(rename = "totalResults")]
     total_results: u32,
     results: Vec<NewsDataIoArticle>,
-    #[serde(rename = "nextPage")]
-    next_page: String,
+    #[serde(rename = "nextPage", default)]
+    next_page: Option<String>,
+}
+
+/// Takes articles until the already seen article is reached.
+/// Articles are ordered from the newest, so everything after the seen article was fetched in the previous pull.
+///
+/// # Returns
+///
+/// * Tuple of unseen articles and flag `true` if the seen article was reached.
+fn take_unseen(
+    results: Vec<NewsDataIoArticle>,
+    last_seen: Option<&str>,
+) -> (Vec<NewsDataIoArticle>, bool) {
+    let Some(last_seen) = last_seen else {
+        return (results, false);
+    };
+    let mut unseen = Vec::with_capacity(results.len());
+    for article in results {
+        if article.article_id == last_seen {
+            return (unseen, true);
+        }
+        unseen.push(article);
+    }
+    (unseen, false)
 }
 
 #[derive(Debug, Clone, Builder)]
@@ -87,59 +275,126 @@ where
     E: ExtractionStrategy<String, String>,
 {
     api_key: String,
+    /// Base URL of the NewsData.io API.
+    #[builder(default = "NEWSDATAIO_URL.to_string()")]
+    base_url: String,
+    #[builder(default)]
+    query: NewsDataIoQuery,
+    #[builder(setter(skip))]
+    client: reqwest::Client,
+    /// Validates the language given by the provider, the article without the language is kept
+    /// so its text can be validated with the `LanguageDetector`.
     lang_validator: V,
     coin_extractor: E,
+    /// Maximum number of pages, that is the API credits, fetched in a single pull.
+    #[builder(default = "DEFAULT_MAX_PAGES")]
+    max_pages: usize,
+    /// Timeout of a single page request.
+    #[builder(default = "DEFAULT_TIMEOUT")]
+    timeout: Duration,
+    /// Cursor of the newest article fetched so far, remembered between the pulls.
+    #[builder(setter(skip))]
+    last_seen: Arc<Mutex<Option<String>>>,
 }
 
-impl<'a> Fetcher<'a, NewsResponse>
-    for NewsDataIoConnector<NewsDataIoLanguageValidator, CoinExtractor>
+impl<'a, V, E> Fetcher<'a, NewsResponse> for NewsDataIoConnector<V, E>
+where
+    V: ValidatorStrategy<String>,
+    E: ExtractionStrategy<String, String>,
 {
-    async fn pull(&self) -> Result<Vec<NewsResponse>, String> {
-        let mut response = self
-            .fetch_latest_crypto()
-            .await
-            .map_err(|e| format!("{e}"))?;
-
-        println!("STATUS: {}", response.status);
-        if response.status.as_str() != SUCCESS {
-            return Err(format!("Response status: {}", response.status));
+    async fn pull(&self) -> Result<Vec<NewsResponse>, Error> {
+        let last_seen = self
+            .last_seen
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let mut results = Vec::new();
+        let mut page: Option<String> = None;
+        for _ in 0..self.max_pages {
+            let response = self.fetch_page(page.as_deref()).await?;
+
+            println!("STATUS: {}", response.status);
+            if response.status.as_str() != SUCCESS {
+                return Err(Error::Provider(format!(
+                    "Response status: {}",
+                    response.status
+                )));
+            }
+
+            let (unseen, reached_seen) = take_unseen(response.results, last_seen.as_deref());
+            results.extend(unseen);
+            if reached_seen {
+                break;
+            }
+            let Some(next_page) = response.next_page else {
+                break;
+            };
+            page = Some(next_page);
         }
 
-        let news = response
-            .results
+        let newest = results.first().map(|n| n.article_id.clone());
+        let news = results
             .iter_mut()
             .filter(|res| {
-                if let Some(lang) = res.language.borrow() {
-                    self.lang_validator.is_valid(lang)
-                } else {
-                    false
-                }
+                res.language
+                    .as_ref()
+                    .is_none_or(|lang| self.lang_validator.is_valid(lang))
             })
             .map(|n| {
-                n.coins = Some([n.title.borrow(), n.description.borrow()].iter().fold(
-                    vec![],
-                    |acc, s| {
-                        if let Some(s) = s {
-                            let mut extracted = self.coin_extractor.extract(s);
-                            extracted.extend(acc);
-                            return extracted;
-                        }
-                        acc
-                    },
-                ));
+                let content = available(n.content.clone());
+                n.coins = Some(
+                    [n.title.borrow(), n.description.borrow(), &content]
+                        .iter()
+                        .fold(vec![], |acc, s| {
+                            if let Some(s) = s {
+                                let mut extracted = self.coin_extractor.extract(s);
+                                extracted.extend(acc);
+                                return extracted;
+                            }
+                            acc
+                        }),
+                );
                 n
             })
             .map(|res| res.to_owned().try_into())
-            .collect::<Result<Vec<NewsResponse>, String>>()?;
+            .collect::<Result<Vec<NewsResponse>, Error>>()?;
+
+        // The cursor moves only after the whole batch is converted so the failed batch is pulled again.
+        if let Some(newest) = newest {
+            *self
+                .last_seen
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(newest);
+        }
 
         Ok(news)
     }
 }
 
-impl NewsDataIoConnector<NewsDataIoLanguageValidator, CoinExtractor> {
-    async fn fetch_latest_crypto(&self) -> Result<NewsDataIoResponse, Box<dyn std::error::Error>> {
-        let resp = reqwest::get(format!("{NEWSDATAIO_URL}{}&q=crypto", self.api_key))
-            .await?
+impl<V, E> NewsDataIoConnector<V, E>
+where
+    V: ValidatorStrategy<String>,
+    E: ExtractionStrategy<String, String>,
+{
+    async fn fetch_page(&self, page: Option<&str>) -> Result<NewsDataIoResponse, Error> {
+        let url = self.query.url(&self.base_url, page)?;
+        let resp = self
+            .client
+            .get(url)
+            .header(API_KEY_HEADER, &self.api_key)
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            return Err(Error::RateLimited(
+                format!("Response status: {status}"),
+                retry_after(resp.headers()),
+            ));
+        }
+        let resp = resp
+            .error_for_status()?
             .json::<NewsDataIoResponse>()
             .await?;
         Ok(resp)
@@ -148,54 +403,251 @@ impl NewsDataIoConnector<NewsDataIoLanguageValidator, CoinExtractor> {
 
 #[cfg(test)]
 mod tests {
+    use crate::error::Error;
+
+    fn article(id: &str) -> super::NewsDataIoArticle {
+        serde_json::from_value(serde_json::json!({
+            "article_id": id,
+            "duplicate": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_deserialize_response_without_next_page() -> Result<(), String> {
+        use super::NewsDataIoResponse;
+
+        for body in [
+            r#"{"status": "success", "totalResults": 0, "results": []}"#,
+            r#"{"status": "success", "totalResults": 0, "results": [], "nextPage": null}"#,
+        ] {
+            let response: NewsDataIoResponse =
+                serde_json::from_str(body).map_err(|e| format!("{e}"))?;
+            assert_eq!(response.next_page, None);
+        }
+
+        let response: NewsDataIoResponse = serde_json::from_str(
+            r#"{"status": "success", "totalResults": 0, "results": [], "nextPage": "abc"}"#,
+        )
+        .map_err(|e| format!("{e}"))?;
+        assert_eq!(response.next_page, Some("abc".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_pub_date_with_timezone() -> Result<(), Error> {
+        use super::parse_pub_date;
+
+        let utc = 1_716_208_496_000;
+        assert_eq!(parse_pub_date("2024-05-20 12:34:56", None)?, utc);
+        assert_eq!(parse_pub_date("2024-05-20 12:34:56", Some("UTC"))?, utc);
+        assert_eq!(
+            parse_pub_date("2024-05-20 18:04:56", Some("Asia/Kolkata"))?,
+            utc
+        );
+        assert_eq!(parse_pub_date("2024-05-20 14:34:56", Some("+02:00"))?, utc);
+        assert!(parse_pub_date("20/05/2024", Some("UTC")).is_err());
+        assert!(parse_pub_date("2024-05-20 12:34:56", Some("Mars/Olympus")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_publication_date_as_created_at() -> Result<(), Error> {
+        use crate::shared::NewsResponse;
+
+        let mut dated = article("1");
+        dated.pub_date = Some("2024-05-20 12:34:56".to_string());
+        dated.pub_date_tz = Some("UTC".to_string());
+        let news: NewsResponse = dated.try_into()?;
+        assert_eq!(*news.created_at(), 1_716_208_496_000);
+        assert!(news.ingested_at() > news.created_at());
+
+        for (date, tz) in [
+            ("20/05/2024", "UTC"),
+            ("2024-05-20 12:34:56", "Mars/Olympus"),
+        ] {
+            let mut malformed = article("2");
+            malformed.pub_date = Some(date.to_string());
+            malformed.pub_date_tz = Some(tz.to_string());
+            let news: NewsResponse = malformed.try_into()?;
+            assert_eq!(news.created_at(), news.ingested_at());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_carry_content_authors_and_source_metadata() -> Result<(), Error> {
+        use crate::shared::{NewsResponse, SentimentLabel};
+
+        let full: super::NewsDataIoArticle = serde_json::from_value(serde_json::json!({
+            "article_id": "1",
+            "description": "Short summary.",
+            "content": "Full article body.",
+            "creator": ["Jane Doe"],
+            "category": ["business"],
+            "country": ["united states of america"],
+            "source_id": "coindesk",
+            "source_name": "CoinDesk",
+            "source_url": "https://www.coindesk.com",
+            "source_priority": 42,
+            "ai_tag": "cryptocurrency, markets",
+            "ai_org": ["SEC"],
+            "ai_region": "ONLY AVAILABLE IN PAID PLANS",
+            "image_url": null,
+            "duplicate": false,
+            "sentiment": "positive",
+            "sentiment_stats": {"positive": 75.0, "neutral": 20.0, "negative": 5.0},
+        }))
+        .map_err(|e| Error::Provider(e.to_string()))?;
+        let news: NewsResponse = full.try_into()?;
+
+        assert_eq!(news.text(), "Short summary.");
+        assert_eq!(news.body(), "Full article body.");
+        assert_eq!(news.authors(), &vec!["Jane Doe".to_string()]);
+        assert_eq!(news.categories(), &vec!["business".to_string()]);
+        assert_eq!(news.source().name(), "CoinDesk");
+        assert_eq!(*news.source().priority(), Some(42));
+        assert_eq!(
+            news.provider_tags().topics(),
+            &vec!["cryptocurrency".to_string(), "markets".to_string()]
+        );
+        assert_eq!(
+            news.provider_tags().organizations(),
+            &vec!["SEC".to_string()]
+        );
+        assert!(news.provider_tags().regions().is_empty());
+        let sentiment = news
+            .provider_sentiment()
+            .clone()
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!(*sentiment.label(), SentimentLabel::Positive);
+        assert!((sentiment.polarity() - 0.7).abs() < 1e-9);
+
+        let mut free = article("2");
+        free.description = Some("Only summary.".to_string());
+        free.content = Some("ONLY AVAILABLE IN PAID PLANS".to_string());
+        free.sentiment = Some("ONLY AVAILABLE IN PAID PLANS".to_string());
+        let news: NewsResponse = free.try_into()?;
+        assert_eq!(news.body(), "Only summary.");
+        assert!(news.provider_sentiment().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_take_articles_until_last_seen() {
+        use super::take_unseen;
+
+        let results = vec![article("3"), article("2"), article("1")];
+
+        let (unseen, reached) = take_unseen(results.clone(), None);
+        assert_eq!(unseen.len(), 3);
+        assert!(!reached);
+
+        let (unseen, reached) = take_unseen(results.clone(), Some("2"));
+        assert_eq!(unseen.len(), 1);
+        assert_eq!(unseen[0].article_id, "3");
+        assert!(reached);
+
+        let (unseen, reached) = take_unseen(results, Some("7"));
+        assert_eq!(unseen.len(), 3);
+        assert!(!reached);
+    }
+
+    #[tokio::test]
+    async fn it_should_pull_with_custom_validator_and_extractor() -> Result<(), Error> {
+        use super::NewsDataIoConnectorBuilder;
+        use crate::newsdata_io::validator::{AllOf, Not};
+        use crate::traits::{ExtractionStrategy, Fetcher, ValidatorStrategy};
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        #[derive(Debug, Clone)]
+        struct Exactly(&'static str);
+
+        impl ValidatorStrategy<String> for Exactly {
+            fn is_valid(&self, value: &String) -> bool {
+                value == self.0
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        struct FirstWord;
+
+        impl ExtractionStrategy<String, String> for FirstWord {
+            fn extract(&self, data: &String) -> Vec<String> {
+                data.split_whitespace()
+                    .take(1)
+                    .map(str::to_string)
+                    .collect()
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "success",
+                "totalResults": 3,
+                "results": [
+                    {"article_id": "3", "title": "Gold shines", "language": "english", "duplicate": false},
+                    {"article_id": "2", "title": "Silver slips", "language": "spanish", "duplicate": false},
+                    {"article_id": "1", "title": "Copper calm", "language": "englishness", "duplicate": false},
+                ],
+            })))
+            .mount(&server)
+            .await;
+
+        let connector = NewsDataIoConnectorBuilder::default()
+            .api_key("key".to_string())
+            .base_url(server.uri())
+            .lang_validator(AllOf::new(
+                Not::new(Exactly("spanish")),
+                Not::new(Exactly("englishness")),
+            ))
+            .coin_extractor(FirstWord)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        let news = connector.pull().await?;
+        assert_eq!(news.len(), 1);
+        assert_eq!(news[0].coins(), &vec!["Gold".to_string()]);
+
+        Ok(())
+    }
+
     #[tokio::test]
-    async fn it_should_call_newsdata_io_for_crypto_news() -> Result<(), String> {
+    async fn it_should_call_newsdata_io_for_crypto_news() -> Result<(), Error> {
         use super::*;
+        use crate::newsdata_io::extractor::{CoinExtractor, CombinedExtractor, TagExtractor};
+        use crate::newsdata_io::validator::NewsDataIoLanguageValidator;
         use crate::traits::Fetcher;
         use dotenv::dotenv;
         use std::env;
 
-        dotenv().map_err(|e| format!("{e}"))?;
-
-        let api_key = env::var("NEWSDATA_IO").map_err(|e| format!("{e}"))?;
-        let lang_validator = NewsDataIoLanguageValidator::try_new(&vec![
-            "english".to_string(),
-            "eng".to_string(),
-            "british".to_string(),
-        ])?;
-
-        let coin_extractor = CoinExtractor::try_new(&vec![
-            "stablecoin".to_string(),
-            "stablecoins".to_string(),
-            "nft".to_string(),
-            "nfts".to_string(),
-            "bitcoin".to_string(),
-            "ethereum".to_string(),
-            "solana".to_string(),
-            "bitcoins".to_string(),
-            "ethereums".to_string(),
-            "solanas".to_string(),
-            "dogecoin".to_string(),
-            "sol".to_string(),
-            "btc".to_string(),
-            "eth".to_string(),
-            "sol".to_string(),
-            "usdt".to_string(),
-            "xrp".to_string(),
-            "bnb".to_string(),
-            "usdc".to_string(),
-            "dodge".to_string(),
-            "doge".to_string(),
-            "ada".to_string(),
-            "steth".to_string(),
-        ])?;
+        dotenv().map_err(|e| Error::Config(e.to_string()))?;
+
+        let api_key = env::var("NEWSDATA_IO").map_err(|e| Error::Config(e.to_string()))?;
+        let config = crate::config::PullConfig::default();
+        let lang_validator = NewsDataIoLanguageValidator::try_new(config.languages())?;
+        let coin_extractor = CombinedExtractor::new(
+            CoinExtractor::try_with_aliases(config.assets())?,
+            TagExtractor::new(config.assets()).with_unknown_cashtags(*config.unknown_cashtags()),
+        );
+
+        let query = crate::newsdata_io::query::NewsDataIoQueryBuilder::default()
+            .keywords(config.keywords().as_str())
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
 
         let news_data_connector = NewsDataIoConnectorBuilder::create_empty()
             .api_key(api_key)
+            .query(query)
             .lang_validator(lang_validator)
             .coin_extractor(coin_extractor)
             .build()
-            .map_err(|e| format!("{e}"))?;
+            .map_err(|e| Error::Config(e.to_string()))?;
 
         let news = news_data_connector.pull().await?;
 
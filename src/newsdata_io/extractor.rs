@@ -1,58 +1,330 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::traits::ExtractionStrategy;
+use crate::{error::Error, traits::ExtractionStrategy};
 use aho_corasick::AhoCorasick;
 
 #[derive(Debug, Clone)]
 pub struct CoinExtractor {
     extractor: AhoCorasick,
+    /// Canonical asset id of every alias, in the order of the aliases.
     coins: Vec<String>,
 }
 
 impl CoinExtractor {
-    pub fn try_new(coins: &Vec<String>) -> Result<Self, String> {
-        let coins_to_match = [
-            (' ', ' '),
-            (' ', '.'),
-            ('(', ','),
-            ('(', ' '),
-            (' ', ')'),
-            ('(', ')'),
-            ('[', ','),
-            ('[', ' '),
-            (' ', ']'),
-            ('[', ']'),
-        ]
-        .iter()
-        .fold(vec![], |acc, (start, stop)| {
-            let mut next_acc = acc;
-            next_acc.extend(
-                coins
-                    .iter()
-                    .map(|c| format!("{start}{c}{stop}").to_string())
-                    .collect::<Vec<String>>(),
-            );
-            next_acc
-        });
+    /// Creates the extractor where every coin is its own asset.
+    pub fn try_new(coins: &Vec<String>) -> Result<Self, Error> {
+        let assets = coins
+            .iter()
+            .map(|c| (c.to_string(), vec![c.to_string()]))
+            .collect::<BTreeMap<String, Vec<String>>>();
+        Self::try_with_aliases(&assets)
+    }
+
+    /// Creates the extractor mapping the alias groups to the canonical asset id,
+    /// such as `BTC` for `bitcoin`, `bitcoins`, `btc` and `xbt`.
+    ///
+    /// * `assets` - Aliases of every canonical asset id.
+    ///
+    /// # Returns
+    ///
+    /// * Success with CoinExtractor or `Error::Config` if the alias belongs to more than one asset.
+    pub fn try_with_aliases(assets: &BTreeMap<String, Vec<String>>) -> Result<Self, Error> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut aliases = vec![];
+        let mut coins = vec![];
+        for (asset, asset_aliases) in assets {
+            for alias in asset_aliases {
+                if !seen.insert(alias.to_lowercase()) {
+                    return Err(Error::Config(format!(
+                        "Alias [ {alias} ] of asset [ {asset} ] belongs to more than one asset"
+                    )));
+                }
+                aliases.push(alias.to_string());
+                coins.push(asset.to_string());
+            }
+        }
 
         let extractor = AhoCorasick::builder()
             .ascii_case_insensitive(true)
-            .build(coins_to_match)
-            .map_err(|e| format!("{e}"))?;
-        let coins = coins.clone();
+            .build(aliases)
+            .map_err(|e| Error::Config(e.to_string()))?;
 
         Ok(Self { extractor, coins })
     }
 }
 
+/// Tells if the character is a part of the word, Unicode letters and digits or the underscore.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Tells if the match is the whole word, not preceded nor followed by the word character.
+fn is_whole_word(data: &str, start: usize, end: usize) -> bool {
+    !data[..start].chars().next_back().is_some_and(is_word_char)
+        && !data[end..].chars().next().is_some_and(is_word_char)
+}
+
 impl ExtractionStrategy<String, String> for CoinExtractor {
     fn extract(&self, data: &String) -> Vec<String> {
         self.extractor
-            .find_iter(data)
-            .map_while(|m| self.coins.get(m.pattern().as_usize() % self.coins.len()))
+            .find_overlapping_iter(data)
+            .filter(|m| is_whole_word(data, m.start(), m.end()))
+            .filter_map(|m| self.coins.get(m.pattern().as_usize()))
             .map(|s| s.to_string())
             .collect::<HashSet<String>>()
             .into_iter()
             .collect()
     }
 }
+
+/// Extracts the assets marked with the cashtags such as `$ETH` and the hashtags such as `#Bitcoin`.
+/// The tag is extracted only if it is the alias of the known asset,
+/// the cashtag of the unknown asset is extracted as the upper case symbol only if enabled,
+/// since the tags such as `$AAPL` or `$USD` are mostly not the crypto assets.
+#[derive(Debug, Clone)]
+pub struct TagExtractor {
+    /// Canonical asset id by the lower case alias.
+    assets: HashMap<String, String>,
+    /// Extracts the cashtags of the unknown assets.
+    unknown_cashtags: bool,
+}
+
+impl TagExtractor {
+    /// Creates the extractor mapping the tags to the canonical asset id.
+    ///
+    /// * `assets` - Aliases of every canonical asset id.
+    pub fn new(assets: &BTreeMap<String, Vec<String>>) -> Self {
+        let assets = assets
+            .iter()
+            .flat_map(|(asset, aliases)| {
+                aliases
+                    .iter()
+                    .chain([asset])
+                    .map(|alias| (alias.to_lowercase(), asset.to_string()))
+            })
+            .collect();
+        Self {
+            assets,
+            unknown_cashtags: false,
+        }
+    }
+
+    /// Extracts the cashtags of the assets that are not configured as the upper case symbols.
+    ///
+    /// * `unknown_cashtags` - Extract the unknown cashtags if true.
+    pub fn with_unknown_cashtags(mut self, unknown_cashtags: bool) -> Self {
+        self.unknown_cashtags = unknown_cashtags;
+        self
+    }
+
+    fn asset_of(&self, marker: char, tag: &str) -> Option<String> {
+        if let Some(asset) = self.assets.get(&tag.to_lowercase()) {
+            return Some(asset.to_string());
+        }
+        if !self.unknown_cashtags {
+            return None;
+        }
+        let is_symbol = (MIN_CASHTAG_LEN..=MAX_CASHTAG_LEN).contains(&tag.chars().count())
+            && tag.chars().all(|c| c.is_ascii_alphanumeric())
+            && tag.starts_with(|c: char| c.is_ascii_alphabetic());
+        (marker == CASHTAG && is_symbol).then(|| tag.to_uppercase())
+    }
+}
+
+const CASHTAG: char = '$';
+const HASHTAG: char = '#';
+const MIN_CASHTAG_LEN: usize = 2;
+const MAX_CASHTAG_LEN: usize = 10;
+
+impl ExtractionStrategy<String, String> for TagExtractor {
+    fn extract(&self, data: &String) -> Vec<String> {
+        let mut assets = HashSet::new();
+        let mut previous: Option<char> = None;
+        for (i, c) in data.char_indices() {
+            let at_word_start = !previous.is_some_and(is_word_char);
+            previous = Some(c);
+            if !(at_word_start && (c == CASHTAG || c == HASHTAG)) {
+                continue;
+            }
+            let tag_start = i + c.len_utf8();
+            let tag_end = data[tag_start..]
+                .find(|c: char| !is_word_char(c))
+                .map_or(data.len(), |end| tag_start + end);
+            if let Some(asset) = self.asset_of(c, &data[tag_start..tag_end]) {
+                assets.insert(asset);
+            }
+        }
+        assets.into_iter().collect()
+    }
+}
+
+/// Chains the extraction strategies, extracts the values with both of them and returns every value once.
+/// Extractors can be nested to chain more than two of them.
+#[derive(Debug, Clone)]
+pub struct CombinedExtractor<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> CombinedExtractor<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T, E, A, B> ExtractionStrategy<T, E> for CombinedExtractor<A, B>
+where
+    E: PartialEq,
+    A: ExtractionStrategy<T, E>,
+    B: ExtractionStrategy<T, E>,
+{
+    fn extract(&self, data: &T) -> Vec<E> {
+        let mut values = self.first.extract(data);
+        for value in self.second.extract(data) {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extractor() -> Result<CoinExtractor, Error> {
+        CoinExtractor::try_with_aliases(&BTreeMap::from([
+            (
+                "BTC".to_string(),
+                vec![
+                    "bitcoin".to_string(),
+                    "bitcoins".to_string(),
+                    "btc".to_string(),
+                ],
+            ),
+            (
+                "ETH".to_string(),
+                vec!["ethereum".to_string(), "eth".to_string()],
+            ),
+            (
+                "SOL".to_string(),
+                vec!["solana".to_string(), "sol".to_string()],
+            ),
+            ("SHIB".to_string(), vec!["shiba inu".to_string()]),
+        ]))
+    }
+
+    fn extract(extractor: &CoinExtractor, headline: &str) -> Vec<String> {
+        let mut coins = extractor.extract(&headline.to_string());
+        coins.sort();
+        coins
+    }
+
+    #[test]
+    fn it_should_match_whole_words_in_tricky_headlines() -> Result<(), Error> {
+        let extractor = extractor()?;
+
+        for (headline, expected) in [
+            ("BTC breaks $100k", vec!["BTC"]),
+            ("Traders pile in to ETH", vec!["ETH"]),
+            (
+                "BTC, ETH and SOL: the weekly winners",
+                vec!["BTC", "ETH", "SOL"],
+            ),
+            ("Analysts: \"Bitcoin\" is back", vec!["BTC"]),
+            ("$BTC and #Ethereum trend on X", vec!["BTC", "ETH"]),
+            ("Ethereum’s upgrade lands", vec!["ETH"]),
+            ("Why bitcoins?", vec!["BTC"]),
+            ("Shiba Inu-themed memes", vec!["SHIB"]),
+            ("(sol)[eth]{btc}", vec!["BTC", "ETH", "SOL"]),
+            ("Solar stocks rise on solid earnings", vec![]),
+            ("Seth bought a bitcoinsomething", vec![]),
+            ("Straßbtc and ßeth are no coins", vec![]),
+            ("btc_usd pair and eth2 staking", vec![]),
+        ] {
+            assert_eq!(extract(&extractor, headline), expected, "{headline}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_extract_every_coin_of_long_text() -> Result<(), Error> {
+        let extractor = extractor()?;
+        let text = format!("{} solana", "no coins here ".repeat(100));
+
+        assert_eq!(extract(&extractor, &text), vec!["SOL"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_extract_canonical_asset_for_every_alias() -> Result<(), Error> {
+        let extractor = CoinExtractor::try_with_aliases(&BTreeMap::from([
+            (
+                "BTC".to_string(),
+                vec!["bitcoin".to_string(), "btc".to_string(), "xbt".to_string()],
+            ),
+            ("ETH".to_string(), vec!["ethereum".to_string()]),
+        ]))?;
+
+        let mut coins = extractor
+            .extract(&" Bitcoin rallies (BTC) while xbt futures and ethereum stall ".to_string());
+        coins.sort();
+        assert_eq!(coins, vec!["BTC".to_string(), "ETH".to_string()]);
+
+        let duplicated = CoinExtractor::try_with_aliases(&BTreeMap::from([
+            ("BTC".to_string(), vec!["btc".to_string()]),
+            ("WBTC".to_string(), vec!["BTC".to_string()]),
+        ]));
+        assert!(duplicated.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_extract_cashtags_and_hashtags() -> Result<(), Error> {
+        let assets = BTreeMap::from([
+            (
+                "BTC".to_string(),
+                vec!["bitcoin".to_string(), "xbt".to_string()],
+            ),
+            ("ETH".to_string(), vec!["ethereum".to_string()]),
+        ]);
+        let extractor = TagExtractor::new(&assets);
+
+        for (post, expected) in [
+            ("$ETH and #Bitcoin lead, $pepe follows", vec!["BTC", "ETH"]),
+            ("$AAPL and $USD close higher", vec![]),
+            ("#xbt #breaking #crypto", vec!["BTC"]),
+            ("Raised $100k, US$5 and a#bitcoin", vec![]),
+            ("$eth.", vec!["ETH"]),
+        ] {
+            let mut tags = extractor.extract(&post.to_string());
+            tags.sort();
+            assert_eq!(tags, expected, "{post}");
+        }
+
+        let unknown = TagExtractor::new(&assets).with_unknown_cashtags(true);
+        for (post, expected) in [
+            (
+                "$ETH and #Bitcoin lead, $pepe follows",
+                vec!["BTC", "ETH", "PEPE"],
+            ),
+            ("#pepe and $1inch are no symbols", vec![]),
+        ] {
+            let mut tags = unknown.extract(&post.to_string());
+            tags.sort();
+            assert_eq!(tags, expected, "{post}");
+        }
+
+        let combined = CombinedExtractor::new(CoinExtractor::try_with_aliases(&assets)?, unknown);
+        let mut coins =
+            combined.extract(&"Bitcoin rallies as $SOL and #ethereum follow".to_string());
+        coins.sort();
+        assert_eq!(coins, vec!["BTC", "ETH", "SOL"]);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 pub mod connector;
 pub mod extractor;
+pub mod query;
 pub mod validator;
@@ -0,0 +1,239 @@
+use crate::error::Error;
+use derive_builder::Builder;
+use reqwest::Url;
+use std::fmt::Display;
+
+/// Maximum number of values NewsData.io accepts for a single list parameter.
+const MAX_LIST_VALUES: usize = 5;
+
+/// NewsData.io endpoint to query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endpoint {
+    /// Latest news from the past 48 hours.
+    #[default]
+    Latest,
+    /// Crypto related news.
+    Crypto,
+    /// Historical news archive.
+    Archive,
+}
+
+impl Endpoint {
+    fn path(&self) -> &str {
+        match self {
+            Self::Latest => "latest",
+            Self::Crypto => "crypto",
+            Self::Archive => "archive",
+        }
+    }
+}
+
+/// Timeframe limiting how old the news can be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timeframe {
+    Hours(u32),
+    Minutes(u32),
+}
+
+impl Display for Timeframe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hours(h) => write!(f, "{h}"),
+            Self::Minutes(m) => write!(f, "{m}m"),
+        }
+    }
+}
+
+/// Typed query to the NewsData.io API.
+#[derive(Debug, Clone, Default, PartialEq, Builder)]
+#[builder(default, setter(into), build_fn(validate = "Self::validate"))]
+pub struct NewsDataIoQuery {
+    endpoint: Endpoint,
+    /// Keywords or phrases searched in the whole article, `q` parameter.
+    #[builder(setter(into, strip_option))]
+    keywords: Option<String>,
+    /// Keywords or phrases searched in the title only, `qInTitle` parameter.
+    #[builder(setter(into, strip_option))]
+    keywords_in_title: Option<String>,
+    categories: Vec<String>,
+    countries: Vec<String>,
+    languages: Vec<String>,
+    domains: Vec<String>,
+    /// Coin symbols, available on the crypto endpoint only.
+    coins: Vec<String>,
+    exclude_categories: Vec<String>,
+    exclude_countries: Vec<String>,
+    exclude_languages: Vec<String>,
+    exclude_domains: Vec<String>,
+    #[builder(setter(into, strip_option))]
+    timeframe: Option<Timeframe>,
+    /// Date in `YYYY-MM-DD` format, available on the archive endpoint only.
+    #[builder(setter(into, strip_option))]
+    from_date: Option<String>,
+    /// Date in `YYYY-MM-DD` format, available on the archive endpoint only.
+    #[builder(setter(into, strip_option))]
+    to_date: Option<String>,
+}
+
+impl NewsDataIoQueryBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.keywords.as_ref().is_some_and(|k| k.is_some())
+            && self.keywords_in_title.as_ref().is_some_and(|k| k.is_some())
+        {
+            return Err("Keywords and keywords in title can not be used together".to_string());
+        }
+        for (name, values) in [
+            ("categories", &self.categories),
+            ("countries", &self.countries),
+            ("languages", &self.languages),
+            ("domains", &self.domains),
+            ("exclude_categories", &self.exclude_categories),
+            ("exclude_countries", &self.exclude_countries),
+            ("exclude_languages", &self.exclude_languages),
+            ("exclude_domains", &self.exclude_domains),
+        ] {
+            if values.as_ref().is_some_and(|v| v.len() > MAX_LIST_VALUES) {
+                return Err(format!("Too many {name}, maximum is {MAX_LIST_VALUES}"));
+            }
+        }
+        let endpoint = self.endpoint.unwrap_or_default();
+        if self.coins.as_ref().is_some_and(|c| !c.is_empty()) && endpoint != Endpoint::Crypto {
+            return Err("Coins can be queried on the crypto endpoint only".to_string());
+        }
+        let has_dates = [&self.from_date, &self.to_date]
+            .iter()
+            .any(|d| d.as_ref().is_some_and(|d| d.is_some()));
+        if has_dates && endpoint != Endpoint::Archive {
+            return Err("Dates can be queried on the archive endpoint only".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl NewsDataIoQuery {
+    /// Builds the query URL. The API key is not a part of the URL and shall be sent in the header.
+    ///
+    /// * `base_url` - Base URL of the API, such as `https://newsdata.io/api/1`.
+    /// * `page` - Optional `nextPage` cursor from the previous response.
+    ///
+    /// # Returns
+    ///
+    /// * Success `Url` or `Error::Config` with message about failure.
+    pub fn url(&self, base_url: &str, page: Option<&str>) -> Result<Url, Error> {
+        let mut url = Url::parse(&format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            self.endpoint.path()
+        ))
+        .map_err(|e| Error::Config(e.to_string()))?;
+
+        let mut pairs: Vec<(&str, String)> = Vec::new();
+        for (name, value) in [
+            ("q", &self.keywords),
+            ("qInTitle", &self.keywords_in_title),
+            ("from_date", &self.from_date),
+            ("to_date", &self.to_date),
+        ] {
+            if let Some(value) = value {
+                pairs.push((name, value.clone()));
+            }
+        }
+        for (name, values) in [
+            ("category", &self.categories),
+            ("country", &self.countries),
+            ("language", &self.languages),
+            ("domain", &self.domains),
+            ("coin", &self.coins),
+            ("excludecategory", &self.exclude_categories),
+            ("excludecountry", &self.exclude_countries),
+            ("excludelanguage", &self.exclude_languages),
+            ("excludedomain", &self.exclude_domains),
+        ] {
+            if !values.is_empty() {
+                pairs.push((name, values.join(",")));
+            }
+        }
+        if let Some(timeframe) = self.timeframe {
+            pairs.push(("timeframe", timeframe.to_string()));
+        }
+        if let Some(page) = page {
+            pairs.push(("page", page.to_string()));
+        }
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://newsdata.io/api/1";
+
+    #[test]
+    fn it_should_build_url_with_encoded_parameters() -> Result<(), Error> {
+        let query = NewsDataIoQueryBuilder::default()
+            .keywords("bitcoin AND etf")
+            .categories(vec!["business".to_string(), "technology".to_string()])
+            .languages(vec!["en".to_string()])
+            .exclude_domains(vec!["example.com".to_string()])
+            .timeframe(Timeframe::Minutes(30))
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        let url = query.url(BASE_URL, Some("next&page"))?;
+        assert_eq!(
+            url.as_str(),
+            "https://newsdata.io/api/1/latest?q=bitcoin+AND+etf&category=business%2Ctechnology&language=en&excludedomain=example.com&timeframe=30m&page=next%26page"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_build_url_for_endpoint_without_parameters() -> Result<(), Error> {
+        let query = NewsDataIoQueryBuilder::default()
+            .endpoint(Endpoint::Crypto)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        assert_eq!(
+            query.url("http://localhost:8080/", None)?.as_str(),
+            "http://localhost:8080/crypto"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_invalid_queries() {
+        assert!(NewsDataIoQueryBuilder::default()
+            .keywords("crypto")
+            .keywords_in_title("bitcoin")
+            .build()
+            .is_err());
+        assert!(NewsDataIoQueryBuilder::default()
+            .countries(
+                vec!["us", "gb", "de", "fr", "pl", "es"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            )
+            .build()
+            .is_err());
+        assert!(NewsDataIoQueryBuilder::default()
+            .coins(vec!["btc".to_string()])
+            .build()
+            .is_err());
+        assert!(NewsDataIoQueryBuilder::default()
+            .endpoint(Endpoint::Archive)
+            .from_date("2025-01-01")
+            .to_date("2025-01-31")
+            .build()
+            .is_ok());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::traits::ValidatorStrategy;
+use crate::{error::Error, traits::ValidatorStrategy};
 use aho_corasick::AhoCorasick;
 
 #[derive(Debug, Clone)]
@@ -7,11 +7,11 @@ pub struct NewsDataIoLanguageValidator {
 }
 
 impl NewsDataIoLanguageValidator {
-    pub fn try_new(langs: &Vec<String>) -> Result<Self, String> {
+    pub fn try_new(langs: &Vec<String>) -> Result<Self, Error> {
         let validator = AhoCorasick::builder()
             .ascii_case_insensitive(true)
             .build(langs)
-            .map_err(|e| format!("{e}"))?;
+            .map_err(|e| Error::Config(e.to_string()))?;
 
         Ok(Self { validator })
     }
@@ -22,3 +22,91 @@ impl ValidatorStrategy<String> for NewsDataIoLanguageValidator {
         self.validator.is_match(value)
     }
 }
+
+/// Valid if both validators accept the value.
+#[derive(Debug, Clone)]
+pub struct AllOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AllOf<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T, A, B> ValidatorStrategy<T> for AllOf<A, B>
+where
+    A: ValidatorStrategy<T>,
+    B: ValidatorStrategy<T>,
+{
+    fn is_valid(&self, value: &T) -> bool {
+        self.first.is_valid(value) && self.second.is_valid(value)
+    }
+}
+
+/// Valid if any of the validators accepts the value.
+#[derive(Debug, Clone)]
+pub struct AnyOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AnyOf<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<T, A, B> ValidatorStrategy<T> for AnyOf<A, B>
+where
+    A: ValidatorStrategy<T>,
+    B: ValidatorStrategy<T>,
+{
+    fn is_valid(&self, value: &T) -> bool {
+        self.first.is_valid(value) || self.second.is_valid(value)
+    }
+}
+
+/// Valid if the validator rejects the value.
+#[derive(Debug, Clone)]
+pub struct Not<A> {
+    inner: A,
+}
+
+impl<A> Not<A> {
+    pub fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T, A> ValidatorStrategy<T> for Not<A>
+where
+    A: ValidatorStrategy<T>,
+{
+    fn is_valid(&self, value: &T) -> bool {
+        !self.inner.is_valid(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_combine_validators() -> Result<(), Error> {
+        let english = NewsDataIoLanguageValidator::try_new(&vec!["english".to_string()])?;
+        let spanish = NewsDataIoLanguageValidator::try_new(&vec!["spanish".to_string()])?;
+        let any = AnyOf::new(english.clone(), spanish.clone());
+        let all = AllOf::new(english.clone(), Not::new(spanish));
+
+        assert!(any.is_valid(&"spanish".to_string()));
+        assert!(!any.is_valid(&"german".to_string()));
+        assert!(all.is_valid(&"english".to_string()));
+        assert!(!all.is_valid(&"english, spanish".to_string()));
+        assert!(!Not::new(english).is_valid(&"english".to_string()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{error::Error, shared::FewShotExample};
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+const VARIABLE_START: &str = "{{";
+const VARIABLE_END: &str = "}}";
+
+/// Named prompt preset with the templated system prompt and the few-shot examples.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default)]
+pub struct Persona {
+    #[serde(default)]
+    description: String,
+    /// System prompt with `{{variable}}` placeholders.
+    prompt: String,
+    /// Default values of the prompt variables.
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    #[serde(default)]
+    few_shot: Vec<FewShotExample>,
+}
+
+impl Persona {
+    /// Renders the prompt replacing every `{{variable}}` placeholder.
+    ///
+    /// * `overrides` - Values replacing the persona default variables.
+    ///
+    /// # Returns
+    ///
+    /// * Success with rendered prompt or `Error::Config` if the placeholder has no value.
+    pub fn render(&self, overrides: &HashMap<String, String>) -> Result<String, Error> {
+        let mut rendered = String::with_capacity(self.prompt.len());
+        let mut rest = self.prompt.as_str();
+        while let Some(start) = rest.find(VARIABLE_START) {
+            let Some(end) = rest[start..].find(VARIABLE_END) else {
+                break;
+            };
+            let name = rest[start + VARIABLE_START.len()..start + end].trim();
+            let value = overrides
+                .get(name)
+                .or(self.variables.get(name))
+                .ok_or(Error::Config(format!(
+                    "No value for prompt variable [ {name} ]"
+                )))?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(value);
+            rest = &rest[start + end + VARIABLE_END.len()..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered.trim().to_string())
+    }
+}
+
+/// Registry of the personas loaded from the directory of TOML and YAML files.
+/// Persona is named after the file stem, `personas/trump.toml` is the `trump` persona.
+#[derive(Debug, Clone, Default)]
+pub struct PersonaRegistry {
+    personas: HashMap<String, Persona>,
+}
+
+impl PersonaRegistry {
+    /// Loads every `.toml`, `.yaml` and `.yml` file from the directory.
+    ///
+    /// * `dir` - Directory with the persona files.
+    ///
+    /// # Returns
+    ///
+    /// * Success with PersonaRegistry or `Error::Config` if the directory or any persona file can not be read.
+    pub fn load_dir(dir: &Path) -> Result<Self, Error> {
+        let entries =
+            fs::read_dir(dir).map_err(|e| Error::Config(format!("{}: {e}", dir.display())))?;
+
+        let mut personas = HashMap::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| Error::Config(format!("{}: {e}", dir.display())))?
+                .path();
+            let (Some(name), Some(extension)) = (
+                path.file_stem().and_then(|n| n.to_str()),
+                path.extension().and_then(|e| e.to_str()),
+            ) else {
+                continue;
+            };
+            let persona = match extension {
+                "toml" => toml::from_str(&read(&path)?).map_err(|e| e.to_string()),
+                "yaml" | "yml" => serde_yaml::from_str(&read(&path)?).map_err(|e| e.to_string()),
+                _ => continue,
+            }
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+            personas.insert(name.to_string(), persona);
+        }
+
+        Ok(Self { personas })
+    }
+
+    /// Finds the persona by name.
+    ///
+    /// # Returns
+    ///
+    /// * Success with Persona or `Error::NotFound` listing the available personas.
+    pub fn get(&self, name: &str) -> Result<&Persona, Error> {
+        self.personas.get(name).ok_or_else(|| {
+            Error::NotFound(format!(
+                "Persona [ {name} ], available are [ {} ]",
+                self.names().join(", ")
+            ))
+        })
+    }
+
+    /// Sorted names of the loaded personas.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.personas.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_should_render_prompt_variables() -> Result<(), Error> {
+        let persona = Persona {
+            prompt: "You are {{ role }} talking about {{topic}}.".to_string(),
+            variables: HashMap::from([
+                ("role".to_string(), "an analyst".to_string()),
+                ("topic".to_string(), "stocks".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            persona.render(&HashMap::new())?,
+            "You are an analyst talking about stocks."
+        );
+        assert_eq!(
+            persona.render(&HashMap::from([(
+                "topic".to_string(),
+                "Bitcoin".to_string()
+            )]))?,
+            "You are an analyst talking about Bitcoin."
+        );
+
+        let persona = Persona {
+            prompt: "You are {{unknown}}.".to_string(),
+            ..Default::default()
+        };
+        assert!(persona.render(&HashMap::new()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_load_toml_and_yaml_personas_from_directory() -> Result<(), Error> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("personas");
+        let registry = PersonaRegistry::load_dir(&dir)?;
+
+        assert_eq!(registry.names(), vec!["sentiment", "trump"]);
+        assert_eq!(registry.get("trump")?.few_shot().len(), 1);
+        assert!(registry
+            .get("sentiment")?
+            .render(&HashMap::new())?
+            .contains("about cryptocurrencies"));
+        assert!(matches!(registry.get("nobody"), Err(Error::NotFound(_))));
+
+        Ok(())
+    }
+}
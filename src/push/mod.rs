@@ -0,0 +1,434 @@
+use crate::{
+    advise::LlmAdvisor,
+    error::Error,
+    sentiment::advise_sentiment,
+    shared::{
+        ConfigurationBuilder, ConversationMode, ModelSettings, SentimentData, SentimentResult,
+    },
+    traits::{Advise, Handler, Store},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tokio::{
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+const DEFAULT_PROMPT: &str = r#"
+You are a financial news analyst. Answer shortly and precisely about the given message.
+"#;
+const SENTIMENT_PROMPT: &str = r#"
+Analyze the text sentiment and provide the result in JSON format like in this example:
+{"negative": 0.0, "neutral": 0.0, "positive": 0.0} where 0.0 is a real value given as a float.
+All the values are between 0.0 and 1.0 and sum up to 1.0.
+"#;
+const SENTIMENT_RETRIES: usize = 2;
+const JOBS_BUFFER: usize = 64;
+
+enum AdviseJob {
+    Advise(String, oneshot::Sender<Result<String, Error>>),
+    Sentiment(String, oneshot::Sender<Result<SentimentResult, Error>>),
+}
+
+enum StoreJob {
+    ReadById(String, oneshot::Sender<Result<SentimentData, Error>>),
+    ReadFromTime(u128, oneshot::Sender<Result<Vec<SentimentData>, Error>>),
+}
+
+#[derive(Clone)]
+struct AppState {
+    advise_jobs: mpsc::Sender<AdviseJob>,
+    store_jobs: mpsc::Sender<StoreJob>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextRequest {
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AdviseResponse {
+    advice: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeRange {
+    /// Inclusive timestamp in [ ms ] from which to read entities.
+    from: u64,
+    /// Optional inclusive timestamp in [ ms ] to which to read entities.
+    to: Option<u64>,
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
+
+fn status_of(e: &Error) -> StatusCode {
+    match e {
+        Error::NotFound(_) => StatusCode::NOT_FOUND,
+        Error::Http(_) | Error::Unauthorized(_) | Error::Provider(_) | Error::InvalidReply(_) => {
+            StatusCode::BAD_GATEWAY
+        }
+        Error::Storage(_) | Error::RateLimited(_, _) | Error::Interrupted => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        Error::ModelLoad(_) | Error::Generation(_) | Error::Config(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Sends the job to the worker and awaits the worker reply.
+async fn dispatch<J, T>(
+    jobs: &mpsc::Sender<J>,
+    job: impl FnOnce(oneshot::Sender<Result<T, Error>>) -> J,
+) -> Result<T, Response> {
+    let (tx, rx) = oneshot::channel();
+    let unavailable = || {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Worker is not running".to_string(),
+        )
+    };
+    jobs.send(job(tx)).await.map_err(|_| unavailable())?;
+    rx.await
+        .map_err(|_| unavailable())?
+        .map_err(|e| error_response(status_of(&e), e.to_string()))
+}
+
+async fn advise(State(state): State<AppState>, Json(req): Json<TextRequest>) -> Response {
+    match dispatch(&state.advise_jobs, |tx| AdviseJob::Advise(req.text, tx)).await {
+        Ok(advice) => Json(AdviseResponse { advice }).into_response(),
+        Err(e) => e,
+    }
+}
+
+async fn sentiment(State(state): State<AppState>, Json(req): Json<TextRequest>) -> Response {
+    match dispatch(&state.advise_jobs, |tx| AdviseJob::Sentiment(req.text, tx)).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => e,
+    }
+}
+
+async fn sentiment_by_id(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match dispatch(&state.store_jobs, |tx| StoreJob::ReadById(id, tx)).await {
+        Ok(data) => Json(data).into_response(),
+        Err(e) => e,
+    }
+}
+
+async fn sentiment_in_range(
+    State(state): State<AppState>,
+    Query(range): Query<TimeRange>,
+) -> Response {
+    match dispatch(&state.store_jobs, |tx| {
+        StoreJob::ReadFromTime(range.from as u128, tx)
+    })
+    .await
+    {
+        Ok(data) => Json(
+            data.into_iter()
+                .filter(|d| range.to.is_none_or(|to| *d.created_at() <= to))
+                .collect::<Vec<SentimentData>>(),
+        )
+        .into_response(),
+        Err(e) => e,
+    }
+}
+
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/advise", post(advise))
+        .route("/sentiment", post(sentiment).get(sentiment_in_range))
+        .route("/sentiment/{id}", get(sentiment_by_id))
+        .with_state(state)
+}
+
+/// Processes the advise jobs one by one, as the model can generate only one response at the time.
+async fn advise_worker(
+    advise: &mut impl Advise,
+    sentiment: &mut impl Advise,
+    mut jobs: mpsc::Receiver<AdviseJob>,
+) {
+    while let Some(job) = jobs.recv().await {
+        match job {
+            AdviseJob::Advise(text, tx) => {
+                let _ = tx.send(advise.advise_about(&text).await);
+            }
+            AdviseJob::Sentiment(text, tx) => {
+                let _ = tx.send(advise_sentiment(sentiment, &text, SENTIMENT_RETRIES).await);
+            }
+        }
+    }
+}
+
+async fn store_worker<S>(store: &S, mut jobs: mpsc::Receiver<StoreJob>)
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    while let Some(job) = jobs.recv().await {
+        match job {
+            StoreJob::ReadById(id, tx) => {
+                let _ = tx.send(store.read_by_id(&id).await);
+            }
+            StoreJob::ReadFromTime(timestamp_ms, tx) => {
+                let _ = tx.send(store.read_from_time(timestamp_ms).await);
+            }
+        }
+    }
+}
+
+/// Serves the REST API with the advise, sentiment and stored sentiment data endpoints.
+async fn serve<S>(
+    listener: TcpListener,
+    advise: &mut impl Advise,
+    sentiment: &mut impl Advise,
+    store: &S,
+) -> Result<(), Error>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    let (advise_tx, advise_rx) = mpsc::channel(JOBS_BUFFER);
+    let (store_tx, store_rx) = mpsc::channel(JOBS_BUFFER);
+    let app = router(AppState {
+        advise_jobs: advise_tx,
+        store_jobs: store_tx,
+    });
+
+    tokio::select! {
+        result = axum::serve(listener, app) => result.map_err(|e| Error::Http(e.to_string())),
+        _ = advise_worker(advise, sentiment, advise_rx) => {
+            Err(Error::Generation("Advise worker stopped".to_string()))
+        }
+        _ = store_worker(store, store_rx) => {
+            Err(Error::Storage("Store worker stopped".to_string()))
+        }
+    }
+}
+
+/// Handles the PushModel execution, serving the REST API over the Advise and the Store.
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct PushModel<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    /// Address the server listens on.
+    #[builder(default = "DEFAULT_ADDR.parse().expect(\"valid default address\")")]
+    addr: SocketAddr,
+    /// Prompt of the Advise provider answering on the advise endpoint.
+    #[builder(default = "DEFAULT_PROMPT.to_string()")]
+    prompt: String,
+    store: S,
+}
+
+impl<S> Handler for PushModel<S>
+where
+    S: for<'a> Store<'a, SentimentData>,
+{
+    async fn run(&self) -> Result<(), Error> {
+        let advise_configuration = ConfigurationBuilder::default()
+            .prompt(self.prompt.clone())
+            .conversation_mode(ConversationMode::Stateless)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let sentiment_configuration = ConfigurationBuilder::default()
+            .prompt(SENTIMENT_PROMPT.to_string())
+            .model_settings(ModelSettings::deterministic())
+            .conversation_mode(ConversationMode::Stateless)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let mut advise = LlmAdvisor::try_new(advise_configuration).await?;
+        let mut sentiment = advise.with_configuration(sentiment_configuration);
+
+        let listener = TcpListener::bind(self.addr)
+            .await
+            .map_err(|e| Error::Config(format!("{}: {e}", self.addr)))?;
+        println!("Push model listens on [ {} ]", self.addr);
+
+        serve(listener, &mut advise, &mut sentiment, &self.store).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{SentimentDataBuilder, SentimentResultBuilder};
+
+    struct EchoAdvisor;
+
+    impl Advise for EchoAdvisor {
+        async fn advise_about(&mut self, msg: &str) -> Result<String, Error> {
+            Ok(format!("echo: {msg}"))
+        }
+    }
+
+    struct SentimentAdvisor;
+
+    impl Advise for SentimentAdvisor {
+        async fn advise_about(&mut self, _msg: &str) -> Result<String, Error> {
+            Ok(r#"{"negative": 0.1, "neutral": 0.2, "positive": 0.7}"#.to_string())
+        }
+    }
+
+    struct MemoryStore {
+        data: Vec<SentimentData>,
+    }
+
+    impl<'a> Store<'a, SentimentData> for MemoryStore {
+        async fn save(&self, _entity: &SentimentData) -> Result<String, Error> {
+            Err(Error::Storage("Read only".to_string()))
+        }
+
+        async fn read_by_id(&self, id: &str) -> Result<SentimentData, Error> {
+            self.data
+                .iter()
+                .find(|d| d.resource_id() == id)
+                .cloned()
+                .ok_or(Error::NotFound(id.to_string()))
+        }
+
+        async fn read_from_time(&self, timestamp_ms: u128) -> Result<Vec<SentimentData>, Error> {
+            Ok(self
+                .data
+                .iter()
+                .filter(|d| *d.created_at() as u128 >= timestamp_ms)
+                .cloned()
+                .collect())
+        }
+
+        async fn read_ingested_from_time(
+            &self,
+            timestamp_ms: u128,
+        ) -> Result<Vec<SentimentData>, Error> {
+            Ok(self
+                .data
+                .iter()
+                .filter(|d| (*d.ingested_at()).max(*d.created_at()) as u128 >= timestamp_ms)
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn sentiment_data(id: &str, created_at: u64) -> SentimentData {
+        SentimentDataBuilder::default()
+            .resource_id(id.to_string())
+            .title("title".to_string())
+            .origin("test".to_string())
+            .text("text".to_string())
+            .link("https://example.com".to_string())
+            .created_at(created_at)
+            .ingested_at(created_at)
+            .coins(vec![])
+            .keywords(vec![])
+            .sentiment(
+                SentimentResultBuilder::default()
+                    .negative(0.0)
+                    .neutral(1.0)
+                    .positive(0.0)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    async fn requests(addr: SocketAddr) -> Result<(), String> {
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}");
+
+        let advice: AdviseResponse = client
+            .post(format!("{url}/advise"))
+            .json(&TextRequest {
+                text: "hello".to_string(),
+            })
+            .send()
+            .await
+            .map_err(|e| format!("{e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("{e}"))?;
+        assert_eq!(advice.advice, "echo: hello");
+
+        let sentiment: SentimentResult = client
+            .post(format!("{url}/sentiment"))
+            .json(&TextRequest {
+                text: "Bitcoin hits new high".to_string(),
+            })
+            .send()
+            .await
+            .map_err(|e| format!("{e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("{e}"))?;
+        assert_eq!(*sentiment.positive(), 0.7);
+
+        let data: SentimentData = client
+            .get(format!("{url}/sentiment/b"))
+            .send()
+            .await
+            .map_err(|e| format!("{e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("{e}"))?;
+        assert_eq!(data.resource_id(), "b");
+
+        let status = client
+            .get(format!("{url}/sentiment/missing"))
+            .send()
+            .await
+            .map_err(|e| format!("{e}"))?
+            .status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let data: Vec<SentimentData> = client
+            .get(format!("{url}/sentiment?from=150&to=250"))
+            .send()
+            .await
+            .map_err(|e| format!("{e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("{e}"))?;
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].resource_id(), "b");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_serve_advise_sentiment_and_stored_data() -> Result<(), String> {
+        let store = MemoryStore {
+            data: vec![
+                sentiment_data("a", 100),
+                sentiment_data("b", 200),
+                sentiment_data("c", 300),
+            ],
+        };
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("{e}"))?;
+        let addr = listener.local_addr().map_err(|e| format!("{e}"))?;
+        let (mut advise, mut sentiment) = (EchoAdvisor, SentimentAdvisor);
+
+        tokio::select! {
+            result = serve(listener, &mut advise, &mut sentiment, &store) => {
+                Err(format!("Server stopped: {result:?}"))
+            }
+            result = requests(addr) => result,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::shared::{SentimentData, SentimentLabel};
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Average sentiment of the articles mentioning the coin.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default)]
+pub struct CoinSentiment {
+    coin: String,
+    articles: usize,
+    negative: f64,
+    neutral: f64,
+    positive: f64,
+}
+
+/// Summarizes the sentiment of the articles per coin they mention.
+///
+/// * `data` - Analyzed articles, the article without coins is not counted.
+///
+/// # Returns
+///
+/// * Vector of CoinSentiment sorted by the coin name.
+pub fn summarize_by_coin(data: &[SentimentData]) -> Vec<CoinSentiment> {
+    let mut coins: BTreeMap<&str, CoinSentiment> = BTreeMap::new();
+    for d in data {
+        for coin in d.coins() {
+            let summary = coins.entry(coin).or_insert_with(|| CoinSentiment {
+                coin: coin.to_string(),
+                ..Default::default()
+            });
+            summary.articles += 1;
+            summary.negative += d.sentiment().negative();
+            summary.neutral += d.sentiment().neutral();
+            summary.positive += d.sentiment().positive();
+        }
+    }
+
+    coins
+        .into_values()
+        .map(|mut summary| {
+            let articles = summary.articles as f64;
+            summary.negative /= articles;
+            summary.neutral /= articles;
+            summary.positive /= articles;
+            summary
+        })
+        .collect()
+}
+
+/// Article the provider and the model labeled differently.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters)]
+pub struct Disagreement {
+    resource_id: String,
+    title: String,
+    provider: SentimentLabel,
+    model: SentimentLabel,
+    /// Absolute difference of the provider and the model polarity.
+    gap: f64,
+}
+
+/// Agreement of the sentiment scored by the news provider and by the model.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default)]
+pub struct SentimentAgreement {
+    /// Number of the articles with the provider sentiment.
+    articles: usize,
+    /// Article counts by the provider label in rows and the model label in columns,
+    /// both ordered as `SentimentLabel::ALL`.
+    confusion: [[usize; 3]; 3],
+    /// Share of the articles labeled the same.
+    accuracy: f64,
+    /// Pearson correlation of the provider and the model polarity,
+    /// `None` if there are less than two articles or a polarity is constant.
+    correlation: Option<f64>,
+    /// Disagreements with the largest polarity gap first.
+    disagreements: Vec<Disagreement>,
+}
+
+/// Compares the model sentiment with the sentiment given by the news provider.
+///
+/// * `data` - Analyzed articles, the article without the provider sentiment is not counted.
+/// * `samples` - Maximum number of the disagreements returned.
+///
+/// # Returns
+///
+/// * SentimentAgreement of the articles with both sentiments.
+pub fn compare_with_provider(data: &[SentimentData], samples: usize) -> SentimentAgreement {
+    let mut agreement = SentimentAgreement::default();
+    let mut polarities = Vec::new();
+    for d in data {
+        let Some(provider) = d.provider_sentiment() else {
+            continue;
+        };
+        let model = d.sentiment().label();
+        agreement.articles += 1;
+        agreement.confusion[*provider.label() as usize][model as usize] += 1;
+        polarities.push((provider.polarity(), d.sentiment().polarity()));
+        if *provider.label() != model {
+            agreement.disagreements.push(Disagreement {
+                resource_id: d.resource_id().to_string(),
+                title: d.title().to_string(),
+                provider: *provider.label(),
+                model,
+                gap: (provider.polarity() - d.sentiment().polarity()).abs(),
+            });
+        }
+    }
+    if agreement.articles == 0 {
+        return agreement;
+    }
+
+    let agreed: usize = (0..SentimentLabel::ALL.len())
+        .map(|i| agreement.confusion[i][i])
+        .sum();
+    agreement.accuracy = agreed as f64 / agreement.articles as f64;
+    agreement.correlation = pearson(&polarities);
+    agreement
+        .disagreements
+        .sort_by(|a, b| b.gap.total_cmp(&a.gap));
+    agreement.disagreements.truncate(samples);
+    agreement
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{
+        ProviderSentimentBuilder, SentimentDataBuilder, SentimentResult, SentimentResultBuilder,
+    };
+
+    fn data(coins: &[&str], negative: f64, neutral: f64, positive: f64) -> SentimentData {
+        data_with_provider(coins, negative, neutral, positive, None)
+    }
+
+    fn data_with_provider(
+        coins: &[&str],
+        negative: f64,
+        neutral: f64,
+        positive: f64,
+        provider: Option<SentimentLabel>,
+    ) -> SentimentData {
+        let provider = provider.map(|label| {
+            ProviderSentimentBuilder::default()
+                .label(label)
+                .scores(None::<SentimentResult>)
+                .build()
+                .unwrap()
+        });
+        SentimentDataBuilder::default()
+            .resource_id("id".to_string())
+            .title("title".to_string())
+            .origin("origin".to_string())
+            .text("text".to_string())
+            .link("link".to_string())
+            .created_at(0_u64)
+            .ingested_at(0_u64)
+            .coins(coins.iter().map(|c| c.to_string()).collect())
+            .keywords(vec![])
+            .provider_sentiment(provider)
+            .sentiment(
+                SentimentResultBuilder::default()
+                    .negative(negative)
+                    .neutral(neutral)
+                    .positive(positive)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn it_should_average_sentiment_per_coin() {
+        let summary = summarize_by_coin(&[
+            data(&["btc", "eth"], 0.2, 0.2, 0.6),
+            data(&["btc"], 0.4, 0.4, 0.2),
+            data(&[], 1.0, 0.0, 0.0),
+        ]);
+
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].coin(), "btc");
+        assert_eq!(*summary[0].articles(), 2);
+        assert!((summary[0].negative() - 0.3).abs() < f64::EPSILON);
+        assert!((summary[0].positive() - 0.4).abs() < f64::EPSILON);
+        assert_eq!(summary[1].coin(), "eth");
+        assert_eq!(*summary[1].articles(), 1);
+    }
+
+    #[test]
+    fn it_should_compare_model_with_provider_sentiment() {
+        let agreement = compare_with_provider(
+            &[
+                data_with_provider(&[], 0.1, 0.2, 0.7, Some(SentimentLabel::Positive)),
+                data_with_provider(&[], 0.8, 0.1, 0.1, Some(SentimentLabel::Negative)),
+                data_with_provider(&[], 0.1, 0.6, 0.3, Some(SentimentLabel::Positive)),
+                data_with_provider(&[], 0.1, 0.1, 0.8, Some(SentimentLabel::Negative)),
+                data(&[], 0.1, 0.1, 0.8),
+            ],
+            1,
+        );
+
+        assert_eq!(*agreement.articles(), 4);
+        assert_eq!(agreement.confusion(), &[[1, 0, 1], [0, 0, 0], [0, 1, 1]]);
+        assert!((agreement.accuracy() - 0.5).abs() < f64::EPSILON);
+        assert!(agreement.correlation().is_some_and(|c| c > 0.0 && c < 1.0));
+        assert_eq!(agreement.disagreements().len(), 1);
+        assert_eq!(
+            *agreement.disagreements()[0].provider(),
+            SentimentLabel::Negative
+        );
+        assert_eq!(
+            *agreement.disagreements()[0].model(),
+            SentimentLabel::Positive
+        );
+
+        let agreement = compare_with_provider(&[data(&[], 0.1, 0.1, 0.8)], 5);
+        assert_eq!(*agreement.articles(), 0);
+        assert!(agreement.correlation().is_none());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{error::Error, traits::Fetcher};
+use chrono::{DateTime, Utc};
+use derive_builder::Builder;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const DEFAULT_MAX_RETRIES: usize = 3;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Credits spent on the day, the day is counted in days since the epoch.
+#[derive(Debug, Clone, Copy, Default)]
+struct Spent {
+    day: u64,
+    credits: u64,
+}
+
+/// Retries the transient failures of any `Fetcher` with the jittered exponential backoff,
+/// waits as long as the provider asks for with the `Retry-After` header
+/// and stops pulling when the daily credit budget is spent until the budget resets at midnight UTC.
+/// The spent credits are kept in memory only, so the budget starts over when the process restarts.
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct RetryFetcher<F> {
+    fetcher: F,
+    /// Number of the retries after the first failed attempt.
+    #[builder(default = "DEFAULT_MAX_RETRIES")]
+    max_retries: usize,
+    /// Delay before the first retry, doubled with every next retry.
+    #[builder(default = "DEFAULT_BASE_DELAY")]
+    base_delay: Duration,
+    /// Maximum delay between the retries, the failure asking to wait longer is returned instead.
+    #[builder(default = "DEFAULT_MAX_DELAY")]
+    max_delay: Duration,
+    /// Credits spent by every attempt, such as the maximum number of the pages pulled.
+    #[builder(default = "1")]
+    credits_per_pull: u64,
+    /// Credits that can be spent per day, unlimited if not set.
+    #[builder(default)]
+    daily_credits: Option<u64>,
+    #[builder(setter(skip))]
+    spent: Arc<Mutex<Spent>>,
+}
+
+impl<'a, T, F> Fetcher<'a, T> for RetryFetcher<F>
+where
+    T: Serialize + Deserialize<'a> + Send,
+    F: Fetcher<'a, T>,
+{
+    async fn pull(&self) -> Result<Vec<T>, Error> {
+        let mut attempt = 0;
+        loop {
+            self.spend(epoch_secs())?;
+            let error = match self.fetcher.pull().await {
+                Ok(pulled) => return Ok(pulled),
+                Err(e) if attempt < self.max_retries && is_retryable(&e) => e,
+                Err(e) => return Err(e),
+            };
+            let delay = match &error {
+                Error::RateLimited(_, Some(after)) => *after,
+                _ => self.backoff(attempt),
+            };
+            if delay > self.max_delay {
+                return Err(error);
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl<F> RetryFetcher<F> {
+    /// Spends the credits of a single attempt.
+    ///
+    /// * `now_s` - Current time in [ s ] since the epoch.
+    ///
+    /// # Returns
+    ///
+    /// * Success if the credits are within the daily budget or `Error::RateLimited` with the time left to the reset.
+    fn spend(&self, now_s: u64) -> Result<(), Error> {
+        let Some(daily_credits) = self.daily_credits else {
+            return Ok(());
+        };
+        let mut spent = self.spent.lock().unwrap_or_else(PoisonError::into_inner);
+        let day = now_s / SECONDS_PER_DAY;
+        if spent.day != day {
+            *spent = Spent { day, credits: 0 };
+        }
+        if spent.credits + self.credits_per_pull > daily_credits {
+            return Err(Error::RateLimited(
+                format!("Daily budget of [ {daily_credits} ] credits is spent"),
+                Some(Duration::from_secs((day + 1) * SECONDS_PER_DAY - now_s)),
+            ));
+        }
+        spent.credits += self.credits_per_pull;
+        Ok(())
+    }
+
+    /// Exponential delay of the retry with the random jitter in its upper half.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << attempt.min(31) as u32)
+            .min(self.max_delay);
+        delay / 2 + (delay / 2).mul_f64(jitter())
+    }
+}
+
+fn is_retryable(e: &Error) -> bool {
+    matches!(e, Error::Http(_) | Error::RateLimited(_, _))
+}
+
+fn epoch_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Random value between 0.0 and 1.0 taken from the randomly seeded std hasher.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// Reads the `Retry-After` header given in seconds or as the HTTP date.
+///
+/// # Returns
+///
+/// * Time to wait before the next request, `None` if the header is missing or malformed.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::newsdata_io::connector::NewsDataIoConnectorBuilder;
+    use crate::newsdata_io::extractor::TagExtractor;
+    use crate::newsdata_io::validator::NewsDataIoLanguageValidator;
+    use crate::shared::NewsResponse;
+    use reqwest::header::HeaderValue;
+    use std::collections::BTreeMap;
+    use std::time::Instant;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn success() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "success",
+            "totalResults": 1,
+            "results": [{"article_id": "1", "title": "Gold shines", "duplicate": false}],
+        }))
+    }
+
+    async fn fetcher(
+        server: &MockServer,
+        daily_credits: Option<u64>,
+    ) -> Result<impl for<'a> Fetcher<'a, NewsResponse>, Error> {
+        let connector = NewsDataIoConnectorBuilder::default()
+            .api_key("key".to_string())
+            .base_url(server.uri())
+            .lang_validator(NewsDataIoLanguageValidator::try_new(&vec![
+                "english".to_string()
+            ])?)
+            .coin_extractor(TagExtractor::new(&BTreeMap::new()))
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        RetryFetcherBuilder::default()
+            .fetcher(connector)
+            .base_delay(Duration::from_millis(10))
+            .max_delay(Duration::from_secs(2))
+            .daily_credits(daily_credits)
+            .build()
+            .map_err(|e| Error::Config(e.to_string()))
+    }
+
+    #[test]
+    fn it_should_read_retry_after_seconds_and_date() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        let date = (Utc::now() + chrono::TimeDelta::seconds(60)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        assert!(retry_after(&headers).is_some_and(|d| d > Duration::from_secs(50)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn it_should_back_off_exponentially_with_jitter() {
+        let fetcher = RetryFetcherBuilder::default()
+            .fetcher(())
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        for (attempt, max) in [(0, 1), (1, 2), (2, 4), (3, 8), (10, 10)] {
+            let delay = fetcher.backoff(attempt);
+            assert!(delay >= Duration::from_secs(max) / 2);
+            assert!(delay <= Duration::from_secs(max));
+        }
+    }
+
+    #[test]
+    fn it_should_reset_credit_budget_daily() {
+        let fetcher = RetryFetcherBuilder::default()
+            .fetcher(())
+            .credits_per_pull(2)
+            .daily_credits(Some(3))
+            .build()
+            .unwrap();
+
+        assert!(fetcher.spend(SECONDS_PER_DAY + 10).is_ok());
+        assert_eq!(
+            fetcher.spend(SECONDS_PER_DAY + 20),
+            Err(Error::RateLimited(
+                "Daily budget of [ 3 ] credits is spent".to_string(),
+                Some(Duration::from_secs(SECONDS_PER_DAY - 20))
+            ))
+        );
+        assert!(fetcher.spend(2 * SECONDS_PER_DAY).is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_should_retry_server_errors_and_honour_retry_after() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(success())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let started = Instant::now();
+        let news = fetcher(&server, None).await?.pull().await?;
+
+        assert_eq!(news.len(), 1);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_not_retry_rejected_requests() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = fetcher(&server, None).await?.pull().await;
+        assert!(matches!(result, Err(Error::Unauthorized(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_stop_pulling_when_daily_credits_are_spent() -> Result<(), Error> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(success())
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let fetcher = fetcher(&server, Some(2)).await?;
+        fetcher.pull().await?;
+        fetcher.pull().await?;
+
+        assert!(matches!(
+            fetcher.pull().await,
+            Err(Error::RateLimited(_, Some(_)))
+        ));
+
+        Ok(())
+    }
+}
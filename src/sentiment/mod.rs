@@ -0,0 +1,233 @@
+use crate::{error::Error, shared::SentimentResult, traits::Advise};
+
+/// Allowed deviation of the sentiment values sum from 1.0.
+const SUM_TOLERANCE: f64 = 0.05;
+
+/// Tokens reserved after the message for the parse error and the correction prompt sent on retry.
+pub const CORRECTION_TOKENS: usize = 128;
+
+const CORRECTION_PROMPT: &str = r#"
+Your previous reply could not be used. Respond only with a single JSON object like in this example:
+{"negative": 0.1, "neutral": 0.2, "positive": 0.7} where each value is a float between 0.0 and 1.0 and all values sum up to 1.0.
+"#;
+
+/// Parses the sentiment from the model reply that may contain noise around the JSON object.
+/// The first JSON object that deserializes in to the valid `SentimentResult` is returned.
+///
+/// * `reply` - Raw reply of the model.
+///
+/// # Returns
+///
+/// * Success `SentimentResult` or `Error::InvalidReply` with message about failure.
+pub fn parse_sentiment(reply: &str) -> Result<SentimentResult, Error> {
+    parse(reply).map_err(Error::InvalidReply)
+}
+
+fn parse(reply: &str) -> Result<SentimentResult, String> {
+    let mut last_err = "No JSON object found in the reply".to_string();
+    for candidate in json_objects(reply) {
+        match serde_json::from_str::<SentimentResult>(candidate) {
+            Ok(result) => match validate(&result) {
+                Ok(()) => return Ok(result),
+                Err(e) => last_err = e,
+            },
+            Err(e) => last_err = e.to_string(),
+        }
+    }
+
+    Err(last_err)
+}
+
+/// Asks the advisor about the sentiment of the message and parses the reply.
+/// When the reply is malformed the advisor is asked again with the message followed by the correction prompt,
+/// so the stateless advisor that forgets the previous turn still scores the message.
+///
+/// * `advise` - Advisor preset with the sentiment prompt.
+/// * `msg` - Message to be analyzed.
+/// * `retries` - Number of correction attempts after the first reply.
+///
+/// # Returns
+///
+/// * Success `SentimentResult` or `Error` describing the last failure.
+pub async fn advise_sentiment(
+    advise: &mut impl Advise,
+    msg: &str,
+    retries: usize,
+) -> Result<SentimentResult, Error> {
+    let mut reply = advise.advise_about(msg).await?;
+    let mut attempt = 0;
+    loop {
+        match parse(&reply) {
+            Ok(result) => return Ok(result),
+            Err(e) if attempt >= retries => {
+                return Err(Error::InvalidReply(format!("[ {reply} ]: {e}")))
+            }
+            Err(e) => {
+                attempt += 1;
+                reply = advise
+                    .advise_about(format!("{msg}\n\nError: {e}\n{CORRECTION_PROMPT}").as_str())
+                    .await?;
+            }
+        }
+    }
+}
+
+fn validate(result: &SentimentResult) -> Result<(), String> {
+    let values = [result.negative(), result.neutral(), result.positive()];
+    if values.iter().any(|v| !v.is_finite() || **v < 0.0 || **v > 1.0) {
+        return Err(format!("Sentiment values out of range [ 0.0, 1.0 ]: {result:?}"));
+    }
+    let sum: f64 = values.into_iter().sum();
+    if (sum - 1.0).abs() > SUM_TOLERANCE {
+        return Err(format!("Sentiment values sum up to {sum} instead of 1.0"));
+    }
+
+    Ok(())
+}
+
+/// Finds all balanced top level JSON objects in the text, ignoring braces inside strings.
+fn json_objects(text: &str) -> Vec<&str> {
+    let mut objects = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' if depth > 0 => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    objects.push(&text[start..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScriptedAdvisor {
+        replies: Vec<String>,
+        asked: Vec<String>,
+    }
+
+    impl Advise for ScriptedAdvisor {
+        async fn advise_about(&mut self, msg: &str) -> Result<String, Error> {
+            self.asked.push(msg.to_string());
+            if self.replies.is_empty() {
+                return Err(Error::Generation("No more replies".to_string()));
+            }
+            Ok(self.replies.remove(0))
+        }
+    }
+
+    #[test]
+    fn it_should_parse_json_surrounded_by_noise() -> Result<(), Error> {
+        let reply = r#"Sure! Here is the result: {"negative": 0.1, "neutral": 0.3, "positive": 0.6} Hope it helps {}"#;
+        let result = parse_sentiment(reply)?;
+        assert_eq!(*result.negative(), 0.1);
+        assert_eq!(*result.neutral(), 0.3);
+        assert_eq!(*result.positive(), 0.6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_json_inside_markdown_block() -> Result<(), Error> {
+        let reply = "```json\n{\n  \"positive\": 0.2,\n  \"negative\": 0.5,\n  \"neutral\": 0.3\n}\n```";
+        let result = parse_sentiment(reply)?;
+        assert_eq!(*result.negative(), 0.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_invalid_replies() {
+        for reply in [
+            "I can not analyze this text.",
+            r#"{"negative": 0.1, "neutral": 0.1}"#,
+            r#"{"negative": 1.5, "neutral": -0.5, "positive": 0.0}"#,
+            r#"{"negative": 0.5, "neutral": 0.5, "positive": 0.5}"#,
+            r#"{"negative": "low", "neutral": 0.5, "positive": 0.5}"#,
+        ] {
+            assert!(parse_sentiment(reply).is_err(), "{reply}");
+        }
+    }
+
+    #[test]
+    fn it_should_skip_braces_inside_strings() -> Result<(), Error> {
+        let reply = r#"{"note": "a } brace", "negative": 0.0, "neutral": 1.0, "positive": 0.0}"#;
+        let result = parse_sentiment(reply)?;
+        assert_eq!(*result.neutral(), 1.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_ask_again_when_reply_is_malformed() -> Result<(), Error> {
+        let mut advisor = ScriptedAdvisor {
+            replies: vec![
+                "It is rather positive.".to_string(),
+                r#"{"negative": 0.0, "neutral": 0.2, "positive": 0.8}"#.to_string(),
+            ],
+            asked: vec![],
+        };
+        let result = advise_sentiment(&mut advisor, "Bitcoin hits new high", 2).await?;
+        assert_eq!(*result.positive(), 0.8);
+        assert_eq!(advisor.asked.len(), 2);
+        assert!(advisor.asked[1].contains("Respond only with a single JSON object"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_resend_message_with_correction_prompt() -> Result<(), Error> {
+        let mut advisor = ScriptedAdvisor {
+            replies: vec![
+                "no".to_string(),
+                r#"{"negative": 0.7, "neutral": 0.2, "positive": 0.1}"#.to_string(),
+            ],
+            asked: vec![],
+        };
+        advise_sentiment(&mut advisor, "Exchange halts withdrawals", 1).await?;
+
+        assert_eq!(advisor.asked[0], "Exchange halts withdrawals");
+        assert!(advisor.asked[1].starts_with("Exchange halts withdrawals\n\nError: "));
+        assert!(advisor.asked[1].contains("Respond only with a single JSON object"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_should_fail_when_retries_are_exhausted() {
+        let mut advisor = ScriptedAdvisor {
+            replies: vec!["no".to_string(), "still no".to_string()],
+            asked: vec![],
+        };
+        let result = advise_sentiment(&mut advisor, "Bitcoin hits new high", 1).await;
+        assert!(matches!(result, Err(Error::InvalidReply(_))));
+        assert_eq!(advisor.asked.len(), 2);
+    }
+}
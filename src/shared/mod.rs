@@ -1,6 +1,33 @@
+use crate::{error::Error, traits::Configur};
 use derive_builder::Builder;
 use derive_getters::Getters;
 use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, path::Path, str::FromStr};
+
+/// The source that published the news.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, Getters, Default, Builder,
+)]
+#[builder(default)]
+pub struct NewsSource {
+    id: String,
+    name: String,
+    url: String,
+    icon: String,
+    /// Rank of the source given by the provider, lower is more popular.
+    priority: Option<u32>,
+}
+
+/// Tags the provider assigned to the news.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, Getters, Default, Builder,
+)]
+#[builder(default)]
+pub struct ProviderTags {
+    topics: Vec<String>,
+    organizations: Vec<String>,
+    regions: Vec<String>,
+}
 
 /// The news response from the service.
 #[derive(
@@ -10,11 +37,35 @@ pub struct NewsResponse {
     id: String,
     title: String,
     origin: String,
+    /// Short description of the news.
     text: String,
+    /// Full content of the news, the description if the content is not available.
+    #[builder(default)]
+    body: String,
     link: String,
+    /// Publication time of the news in [ ms ] since the epoch.
     created_at: u64,
+    /// Time the news was fetched in [ ms ] since the epoch.
+    ingested_at: u64,
     coins: Vec<String>,
     keywords: Vec<String>,
+    #[builder(default)]
+    authors: Vec<String>,
+    #[builder(default)]
+    categories: Vec<String>,
+    #[builder(default)]
+    countries: Vec<String>,
+    #[builder(default)]
+    image_url: Option<String>,
+    #[builder(default)]
+    source: NewsSource,
+    #[builder(default)]
+    provider_tags: ProviderTags,
+    #[builder(default)]
+    provider_sentiment: Option<ProviderSentiment>,
+    /// The provider flagged the news as a duplicate of the news it already gave.
+    #[builder(default)]
+    duplicate: bool,
 }
 
 /// The SentimentData contains data about the sentiment.
@@ -26,13 +77,68 @@ pub struct SentimentData {
     title: String,
     origin: String,
     text: String,
+    #[serde(default)]
+    #[builder(default)]
+    body: String,
     link: String,
+    /// Publication time of the news in [ ms ] since the epoch.
     created_at: u64,
+    /// Time the news was fetched in [ ms ] since the epoch, 0 for the data stored before it was recorded.
+    #[serde(default)]
+    #[builder(default)]
+    ingested_at: u64,
     coins: Vec<String>,
     keywords: Vec<String>,
+    #[serde(default)]
+    #[builder(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    #[builder(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    #[builder(default)]
+    countries: Vec<String>,
+    #[serde(default)]
+    #[builder(default)]
+    image_url: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    source: NewsSource,
+    #[serde(default)]
+    #[builder(default)]
+    provider_tags: ProviderTags,
+    /// Sentiment scored by the news provider, compared with the model sentiment.
+    #[serde(default)]
+    #[builder(default)]
+    provider_sentiment: Option<ProviderSentiment>,
     sentiment: SentimentResult,
 }
 
+impl From<(&NewsResponse, SentimentResult)> for SentimentData {
+    fn from((news, sentiment): (&NewsResponse, SentimentResult)) -> Self {
+        Self {
+            resource_id: news.id.clone(),
+            title: news.title.clone(),
+            origin: news.origin.clone(),
+            text: news.text.clone(),
+            body: news.body.clone(),
+            link: news.link.clone(),
+            created_at: news.created_at,
+            ingested_at: news.ingested_at,
+            coins: news.coins.clone(),
+            keywords: news.keywords.clone(),
+            authors: news.authors.clone(),
+            categories: news.categories.clone(),
+            countries: news.countries.clone(),
+            image_url: news.image_url.clone(),
+            source: news.source.clone(),
+            provider_tags: news.provider_tags.clone(),
+            provider_sentiment: news.provider_sentiment.clone(),
+            sentiment,
+        }
+    }
+}
+
 /// The sentiment callculation.
 #[derive(
     Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, Getters, Default, Builder,
@@ -42,3 +148,456 @@ pub struct SentimentResult {
     positive: f64,
     neutral: f64,
 }
+
+impl SentimentResult {
+    /// Label of the highest score, neutral wins the ties.
+    pub fn label(&self) -> SentimentLabel {
+        if self.positive > self.neutral && self.positive > self.negative {
+            SentimentLabel::Positive
+        } else if self.negative > self.neutral && self.negative > self.positive {
+            SentimentLabel::Negative
+        } else {
+            SentimentLabel::Neutral
+        }
+    }
+
+    /// Polarity between -1.0 for the negative and 1.0 for the positive sentiment.
+    pub fn polarity(&self) -> f64 {
+        self.positive - self.negative
+    }
+}
+
+/// Dominant sentiment of the text.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SentimentLabel {
+    Negative,
+    Neutral,
+    Positive,
+}
+
+impl SentimentLabel {
+    /// All the labels ordered from the negative to the positive.
+    pub const ALL: [SentimentLabel; 3] = [Self::Negative, Self::Neutral, Self::Positive];
+
+    /// Polarity of the label, -1.0, 0.0 or 1.0.
+    pub fn polarity(&self) -> f64 {
+        match self {
+            Self::Negative => -1.0,
+            Self::Neutral => 0.0,
+            Self::Positive => 1.0,
+        }
+    }
+}
+
+impl FromStr for SentimentLabel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "negative" => Ok(Self::Negative),
+            "neutral" => Ok(Self::Neutral),
+            "positive" => Ok(Self::Positive),
+            _ => Err(Error::Config(format!("Unknown sentiment label [ {s} ]"))),
+        }
+    }
+}
+
+impl Display for SentimentLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Negative => write!(f, "negative"),
+            Self::Neutral => write!(f, "neutral"),
+            Self::Positive => write!(f, "positive"),
+        }
+    }
+}
+
+/// Sentiment of the news scored by the news provider.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, Getters, Builder)]
+pub struct ProviderSentiment {
+    label: SentimentLabel,
+    /// Scores normalized to sum up to 1.0, if the provider gave them.
+    #[builder(default)]
+    scores: Option<SentimentResult>,
+}
+
+impl ProviderSentiment {
+    /// Polarity of the scores, or of the label if the provider gave no scores.
+    pub fn polarity(&self) -> f64 {
+        self.scores
+            .as_ref()
+            .map_or(self.label.polarity(), SentimentResult::polarity)
+    }
+}
+
+/// Source of the model weights.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub enum ModelSource {
+    #[default]
+    Llama3_1_8bChat,
+    Llama3_2_1bChat,
+    Llama3_2_3bChat,
+    Mistral7bInstruct,
+    Phi3_5MiniInstruct,
+    Qwen2_5_7bInstruct,
+    /// GGUF weights from the Hugging Face repository.
+    /// The file is resolved as `<file_stem>-<quantization>.gguf` and chats with the Llama 3 instruct template.
+    HuggingFace {
+        repo: String,
+        file_stem: String,
+        tokenizer_repo: String,
+    },
+}
+
+impl FromStr for ModelSource {
+    type Err = Error;
+
+    /// Parses one of the preset names or `hf:<repo>:<file_stem>:<tokenizer_repo>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "llama-3.1-8b-chat" => Ok(Self::Llama3_1_8bChat),
+            "llama-3.2-1b-chat" => Ok(Self::Llama3_2_1bChat),
+            "llama-3.2-3b-chat" => Ok(Self::Llama3_2_3bChat),
+            "mistral-7b-instruct" => Ok(Self::Mistral7bInstruct),
+            "phi-3.5-mini-instruct" => Ok(Self::Phi3_5MiniInstruct),
+            "qwen-2.5-7b-instruct" => Ok(Self::Qwen2_5_7bInstruct),
+            _ => match s.split(':').collect::<Vec<&str>>().as_slice() {
+                ["hf", repo, file_stem, tokenizer_repo] => Ok(Self::HuggingFace {
+                    repo: repo.to_string(),
+                    file_stem: file_stem.to_string(),
+                    tokenizer_repo: tokenizer_repo.to_string(),
+                }),
+                _ => Err(Error::Config(format!("Unknown model source [ {s} ]"))),
+            },
+        }
+    }
+}
+
+impl Display for ModelSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Llama3_1_8bChat => write!(f, "llama-3.1-8b-chat"),
+            Self::Llama3_2_1bChat => write!(f, "llama-3.2-1b-chat"),
+            Self::Llama3_2_3bChat => write!(f, "llama-3.2-3b-chat"),
+            Self::Mistral7bInstruct => write!(f, "mistral-7b-instruct"),
+            Self::Phi3_5MiniInstruct => write!(f, "phi-3.5-mini-instruct"),
+            Self::Qwen2_5_7bInstruct => write!(f, "qwen-2.5-7b-instruct"),
+            Self::HuggingFace {
+                repo,
+                file_stem,
+                tokenizer_repo,
+            } => write!(f, "hf:{repo}:{file_stem}:{tokenizer_repo}"),
+        }
+    }
+}
+
+/// Quantization of the GGUF model weights.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantization {
+    Q4_0,
+    #[default]
+    Q4KM,
+    Q5KM,
+    Q6K,
+    Q8_0,
+    F16,
+}
+
+impl Display for Quantization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Q4_0 => "Q4_0",
+            Self::Q4KM => "Q4_K_M",
+            Self::Q5KM => "Q5_K_M",
+            Self::Q6K => "Q6_K",
+            Self::Q8_0 => "Q8_0",
+            Self::F16 => "F16",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Quantization {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "Q4_0" => Ok(Self::Q4_0),
+            "Q4_K_M" => Ok(Self::Q4KM),
+            "Q5_K_M" => Ok(Self::Q5KM),
+            "Q6_K" => Ok(Self::Q6K),
+            "Q8_0" => Ok(Self::Q8_0),
+            "F16" => Ok(Self::F16),
+            _ => Err(Error::Config(format!("Unknown quantization [ {s} ]"))),
+        }
+    }
+}
+
+/// The model selection and sampling parameters.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Builder)]
+#[builder(default)]
+pub struct ModelSettings {
+    source: ModelSource,
+    quantization: Quantization,
+    /// Maximum number of tokens kept in the chat context.
+    context_size: u32,
+    temperature: f64,
+    /// Nucleus sampling probability, applied by the OpenAI backend only.
+    top_p: f64,
+    /// Seed of the sampler, random when not set, applied by the OpenAI backend only.
+    #[builder(setter(strip_option))]
+    seed: Option<u64>,
+    /// Maximum number of tokens generated in a single response.
+    max_tokens: u32,
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        Self {
+            source: ModelSource::default(),
+            quantization: Quantization::default(),
+            context_size: 4096,
+            temperature: 0.8,
+            top_p: 0.95,
+            seed: None,
+            max_tokens: 1024,
+        }
+    }
+}
+
+impl ModelSettings {
+    /// Settings for the reproducible scoring, such as sentiment analysis, with the seeded greedy sampling.
+    pub fn deterministic() -> Self {
+        Self {
+            temperature: 0.0,
+            top_p: 1.0,
+            seed: Some(0),
+            max_tokens: 128,
+            ..Self::default()
+        }
+    }
+}
+
+/// The example exchange preceding the conversation to show the model the expected answers.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default, Builder)]
+pub struct FewShotExample {
+    user: String,
+    assistant: String,
+}
+
+/// Tells how the messages sent to the model relate to each other.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConversationMode {
+    /// Every message continues the conversation with all the previous messages in the context.
+    #[default]
+    Continuous,
+    /// Every message starts from the system prompt and the few-shot examples only.
+    Stateless,
+}
+
+/// The configuration with the prompt and the model settings.
+#[derive(Debug, Clone, PartialEq, Getters, Builder)]
+pub struct Configuration {
+    prompt: String,
+    #[builder(default)]
+    model_settings: ModelSettings,
+    #[builder(default)]
+    conversation_mode: ConversationMode,
+    #[builder(default)]
+    few_shot: Vec<FewShotExample>,
+}
+
+impl Configur for Configuration {
+    fn prompt(&self) -> String {
+        self.prompt.clone()
+    }
+
+    fn model_settings(&self) -> ModelSettings {
+        self.model_settings.clone()
+    }
+
+    fn conversation_mode(&self) -> ConversationMode {
+        self.conversation_mode
+    }
+
+    fn few_shot(&self) -> Vec<FewShotExample> {
+        self.few_shot.clone()
+    }
+}
+
+/// A single exchange of the conversation, the user message and the model answer to it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default, Builder)]
+pub struct Turn {
+    user: String,
+    assistant: String,
+}
+
+impl Turn {
+    pub fn new(user: &str, assistant: &str) -> Self {
+        Self {
+            user: user.to_string(),
+            assistant: assistant.to_string(),
+        }
+    }
+}
+
+/// The chat session with the system prompt and the conversation turns that can be saved and resumed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Getters, Default, Builder)]
+pub struct Session {
+    system_prompt: String,
+    #[builder(default)]
+    turns: Vec<Turn>,
+}
+
+impl Session {
+    pub fn new(system_prompt: &str, turns: Vec<Turn>) -> Self {
+        Self {
+            system_prompt: system_prompt.to_string(),
+            turns,
+        }
+    }
+
+    /// Saves the session as JSON file.
+    ///
+    /// * `path` - Path to the file, overwritten if exists.
+    ///
+    /// # Returns
+    ///
+    /// * Success if saved or `Error::Storage` otherwise.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| Error::Storage(e.to_string()))?;
+        fs::write(path, json).map_err(|e| Error::Storage(format!("{}: {e}", path.display())))
+    }
+
+    /// Renders the session as Markdown document.
+    ///
+    /// # Returns
+    ///
+    /// * String with the system prompt and every turn under its own heading.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# Chat session\n\n**System:** {}\n", self.system_prompt);
+        for (i, turn) in self.turns.iter().enumerate() {
+            markdown.push_str(&format!(
+                "\n## Turn {}\n\n**User:** {}\n\n**Assistant:** {}\n",
+                i + 1,
+                turn.user,
+                turn.assistant
+            ));
+        }
+        markdown
+    }
+
+    /// Loads the session from JSON file.
+    ///
+    /// * `path` - Path to the file saved with `Session::save`.
+    ///
+    /// # Returns
+    ///
+    /// * Success with Session or `Error::Storage` if the file can not be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| Error::Storage(format!("{}: {e}", path.display())))?;
+        serde_json::from_str(&json).map_err(|e| Error::Storage(format!("{}: {e}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_deserialize_sentiment_data_stored_before_new_fields() -> Result<(), Error> {
+        let data: SentimentData = serde_json::from_str(
+            r#"{"resource_id": "1", "title": "title", "origin": "origin", "text": "text",
+                "link": "link", "created_at": 10, "coins": ["BTC"], "keywords": [],
+                "sentiment": {"negative": 0.1, "positive": 0.7, "neutral": 0.2}}"#,
+        )
+        .map_err(|e| Error::Storage(e.to_string()))?;
+
+        assert_eq!(*data.ingested_at(), 0);
+        assert!(data.body().is_empty());
+        assert_eq!(*data.provider_sentiment(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_model_source() -> Result<(), Error> {
+        assert_eq!(
+            "qwen-2.5-7b-instruct".parse::<ModelSource>()?,
+            ModelSource::Qwen2_5_7bInstruct
+        );
+        assert_eq!(
+            "hf:bartowski/Meta-Llama-3.1-8B-Instruct-GGUF:Meta-Llama-3.1-8B-Instruct:meta-llama/Llama-3.1-8B-Instruct"
+                .parse::<ModelSource>()?,
+            ModelSource::HuggingFace {
+                repo: "bartowski/Meta-Llama-3.1-8B-Instruct-GGUF".to_string(),
+                file_stem: "Meta-Llama-3.1-8B-Instruct".to_string(),
+                tokenizer_repo: "meta-llama/Llama-3.1-8B-Instruct".to_string(),
+            }
+        );
+        assert_eq!(
+            ModelSource::Mistral7bInstruct
+                .to_string()
+                .parse::<ModelSource>()?,
+            ModelSource::Mistral7bInstruct
+        );
+        assert!("gpt-42".parse::<ModelSource>().is_err());
+        assert!("hf:repo-only".parse::<ModelSource>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_parse_quantization() -> Result<(), Error> {
+        for quantization in [
+            Quantization::Q4_0,
+            Quantization::Q4KM,
+            Quantization::Q5KM,
+            Quantization::Q6K,
+            Quantization::Q8_0,
+            Quantization::F16,
+        ] {
+            assert_eq!(
+                quantization.to_string().parse::<Quantization>()?,
+                quantization
+            );
+        }
+        assert!("q3".parse::<Quantization>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_save_and_load_session() -> Result<(), Error> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| Error::Storage(e.to_string()))?
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "uncle_good_advice_session_{}_{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).map_err(|e| Error::Storage(e.to_string()))?;
+        let path = dir.join("session.json");
+        let session = Session::new(
+            "You are a crypto analyst.",
+            vec![
+                Turn::new("Is BTC up?", "Yes."),
+                Turn::new("And ETH?", "No."),
+            ],
+        );
+
+        session.save(&path)?;
+        let loaded = Session::load(&path);
+        let _ = fs::remove_file(&path);
+        let missing = Session::load(&path);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(loaded?, session);
+        assert!(missing.is_err());
+
+        Ok(())
+    }
+}
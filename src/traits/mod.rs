@@ -1,6 +1,11 @@
 // Abstractions that settle all the contracts in the uncle_good_advice library.
 // Particular types in the library implement the traits so the dependencies are loosely coupled
 // and can be echanged by any other implementation given by the user.;
+use crate::{
+    error::Error,
+    shared::{ConversationMode, FewShotExample, ModelSettings, Session},
+};
+use futures_util::Stream;
 use serde::ser::Serialize;
 use serde::Deserialize;
 use std::future::Future;
@@ -14,6 +19,33 @@ pub trait Configur {
     ///
     /// * String with prompt message.
     fn prompt(&self) -> String;
+
+    /// Model selection and sampling parameters from configuration.
+    ///
+    /// # Returns
+    ///
+    /// * ModelSettings, the defaults if not overridden.
+    fn model_settings(&self) -> ModelSettings {
+        ModelSettings::default()
+    }
+
+    /// Conversation mode from configuration.
+    ///
+    /// # Returns
+    ///
+    /// * ConversationMode, `ConversationMode::Continuous` if not overridden.
+    fn conversation_mode(&self) -> ConversationMode {
+        ConversationMode::Continuous
+    }
+
+    /// Few-shot examples following the prompt at the start of every conversation.
+    ///
+    /// # Returns
+    ///
+    /// * Vector of FewShotExample, empty if not overridden.
+    fn few_shot(&self) -> Vec<FewShotExample> {
+        vec![]
+    }
 }
 
 /// Advise requires from entity to be able to advise and analyze by responding to given message.
@@ -26,8 +58,36 @@ pub trait Advise {
     ///
     /// # Returns
     ///
-    /// * Future with Success `String` that is an analyze result or Error `String` with message about failure.
-    fn advise_about(&mut self, msg: &str) -> impl Future<Output = Result<String, String>>;
+    /// * Future with Success `String` that is an analyze result or `Error` describing the failure.
+    fn advise_about(&mut self, msg: &str) -> impl Future<Output = Result<String, Error>>;
+}
+
+/// AdviseStream requires from entity to be able to advise by streaming the response as it is generated.
+pub trait AdviseStream {
+    /// Analyzes the message and streams the response in text chunks as they are generated.
+    /// Dropping the stream cancels the generation.
+    ///
+    /// * `msg` - Message to be analyzed  by the entity.
+    ///
+    /// # Returns
+    ///
+    /// * Stream of Success `String` text chunks or `Error` describing the failure.
+    fn advise_stream(&mut self, msg: &str) -> impl Stream<Item = Result<String, Error>> + '_;
+}
+
+/// Converse requires from entity to expose the conversation so it can be saved and resumed.
+pub trait Converse {
+    /// Snapshot of the current conversation.
+    ///
+    /// # Returns
+    ///
+    /// * Session with the system prompt and the turns kept in the context.
+    fn session(&self) -> Session;
+
+    /// Replaces the current conversation with the given one.
+    ///
+    /// * `session` - Session to continue the conversation from.
+    fn restore(&mut self, session: Session);
 }
 
 /// Handler requires from entity to handle the process of the whole program. This can be server, cli tool or cron runner.
@@ -36,8 +96,8 @@ pub trait Handler {
     ///
     /// # Returns
     ///
-    /// * Future with Success `()` if runner runs without issue or Error `String` with message about failure.
-    fn run(&self) -> impl Future<Output = Result<(), String>>;
+    /// * Future with Success `()` if runner runs without issue or `Error` describing the failure.
+    fn run(&self) -> impl Future<Output = Result<(), Error>>;
 }
 
 /// Store requires from entity to have storage capability for the entity. It might be a permanent storage or any form of cache.
@@ -53,8 +113,8 @@ where
     ///
     /// # Returns
     ///
-    /// * Future with Success `String` os saved entity ID or Error `String` with message about failure.
-    fn save(&self, entity: &T) -> impl Future<Output = Result<String, String>>;
+    /// * Future with Success `String` os saved entity ID or `Error` describing the failure.
+    fn save(&self, entity: &T) -> impl Future<Output = Result<String, Error>>;
 
     /// Read by id serializeble entity from the storage.
     ///
@@ -65,8 +125,8 @@ where
     /// # Returns
     ///
     /// * Future with Success entity of type T from storage
-    /// or Error `String` with message about failure.
-    fn read_by_id(&self, id: &str) -> impl Future<Output = Result<T, String>>;
+    /// or `Error` describing the failure, `Error::NotFound` if there is no entity with given id.
+    fn read_by_id(&self, id: &str) -> impl Future<Output = Result<T, Error>>;
 
     /// Reads entities from given time from the storage.
     ///
@@ -77,8 +137,23 @@ where
     /// # Returns
     ///
     /// * Future with Success `Vec<T> with vector of type `T` entities
-    /// or Error `String` with message about failure.
-    fn read_from_time(&self, timestamp_ms: u128) -> impl Future<Output = Result<Vec<T>, String>>;
+    /// or `Error` describing the failure.
+    fn read_from_time(&self, timestamp_ms: u128) -> impl Future<Output = Result<Vec<T>, Error>>;
+
+    /// Reads entities ingested or created from given time from the storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_ms` - inclusive timestamp in [ ms ] from which to read entities.
+    ///
+    /// # Returns
+    ///
+    /// * Future with Success `Vec<T>` with vector of type `T` entities
+    ///   or `Error` describing the failure.
+    fn read_ingested_from_time(
+        &self,
+        timestamp_ms: u128,
+    ) -> impl Future<Output = Result<Vec<T>, Error>>;
 }
 
 /// Fetcher requires from entity to have fetching capability. It shall fetch data from external resource.
@@ -91,8 +166,8 @@ where
     /// # Returns
     ///
     /// * Future with Success `Vec<T>` entities from the source
-    /// or Error `String` with message about failure.
-    fn pull(&self) -> impl Future<Output = Result<Vec<T>, String>>;
+    /// or `Error` describing the failure.
+    fn pull(&self) -> impl Future<Output = Result<Vec<T>, Error>>;
 }
 
 /// FilterStr requires from entity to have capabilities of filtering things from str buffer and returning them as slice of `T` type.